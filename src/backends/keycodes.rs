@@ -208,4 +208,6 @@ pub enum VirtualKeyCode {
     Copy,
     Paste,
     Cut,
+    BrightnessUp,
+    BrightnessDown,
 }
@@ -0,0 +1,389 @@
+//! an in-memory backend for testing the window manager without an X server.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, VecDeque},
+    time::Duration,
+};
+
+use x11::xlib::Window;
+
+use super::{
+    structs::{CursorShape, Icon, Strut, WindowType},
+    window_event::{KeyOrMouseBind, WindowEvent},
+    BackendConfig, WindowServerBackend,
+};
+use crate::util::{Point, Rect, Size};
+
+/// calls to the backend that change the state of a window.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Call {
+    Focus(Window),
+    Unfocus(Window),
+    Raise(Window),
+    Lower(Window),
+    Hide(Window),
+    Minimize(Window),
+    Restore(Window),
+    Kill(Window),
+    ForceKill(Window),
+    Ping(Window),
+    Configure(Window),
+    Bell,
+}
+
+#[derive(Debug, Clone)]
+pub struct MockWindow {
+    pub position: Point<i32>,
+    pub size: Size<i32>,
+    pub border: i32,
+    pub name: Option<String>,
+    pub role: Option<String>,
+    pub window_type: WindowType,
+    pub parent: Option<Window>,
+    pub strut: Option<Strut>,
+    pub desktop: Option<usize>,
+    pub pid: Option<u32>,
+    pub aspect: Option<(f32, f32)>,
+    pub frame_extents: Option<i32>,
+    pub urgent: bool,
+}
+
+impl Default for MockWindow {
+    fn default() -> Self {
+        Self {
+            position: Point::new(0, 0),
+            size: Size::new(100, 100),
+            border: 0,
+            name: None,
+            role: None,
+            window_type: WindowType::Normal,
+            parent: None,
+            strut: None,
+            desktop: None,
+            pid: None,
+            aspect: None,
+            frame_extents: None,
+            urgent: false,
+        }
+    }
+}
+
+pub struct MockBackend {
+    screen_size: Size<i32>,
+    windows: RefCell<BTreeMap<Window, MockWindow>>,
+    calls: RefCell<Vec<Call>>,
+    events: VecDeque<WindowEvent<Window>>,
+    keybinds: Vec<KeyOrMouseBind>,
+    layout_name: RefCell<Option<String>>,
+    work_areas: RefCell<Vec<Rect>>,
+    config: BackendConfig,
+    flushes: Cell<usize>,
+}
+
+impl MockBackend {
+    /// adds a window the window manager can query, without mapping it.
+    pub fn add_window(&self, window: Window, mock: MockWindow) {
+        self.windows.borrow_mut().insert(window, mock);
+    }
+
+    pub fn window(&self, window: Window) -> Option<MockWindow> {
+        self.windows.borrow().get(&window).cloned()
+    }
+
+    pub fn config(&self) -> &BackendConfig {
+        &self.config
+    }
+
+    /// returns how often `flush` was called.
+    pub fn flushes(&self) -> usize {
+        self.flushes.get()
+    }
+
+    pub fn work_areas(&self) -> Vec<Rect> {
+        self.work_areas.borrow().clone()
+    }
+
+    pub fn layout_name(&self) -> Option<String> {
+        self.layout_name.borrow().clone()
+    }
+
+    /// returns the currently grabbed key and mouse binds.
+    pub fn keybinds(&self) -> &[KeyOrMouseBind] {
+        &self.keybinds
+    }
+
+    /// returns the recorded calls and clears them.
+    pub fn take_calls(&self) -> Vec<Call> {
+        self.calls.take()
+    }
+
+    /// queues an event to be returned by `next_event`.
+    pub fn push_event(&mut self, event: WindowEvent<Window>) {
+        self.events.push_back(event);
+    }
+
+    fn record(&self, call: Call) {
+        self.calls.borrow_mut().push(call);
+    }
+}
+
+impl WindowServerBackend for MockBackend {
+    type Window = Window;
+
+    fn build_with(config: BackendConfig) -> Self {
+        Self {
+            screen_size: Size::new(1000, 800),
+            windows: Default::default(),
+            calls: Default::default(),
+            events: Default::default(),
+            keybinds: Vec::new(),
+            layout_name: Default::default(),
+            work_areas: Default::default(),
+            config,
+            flushes: Cell::new(0),
+        }
+    }
+
+    fn next_event(&mut self) -> WindowEvent<Self::Window> {
+        self.events.pop_front().expect("no more queued events")
+    }
+
+    fn next_event_timeout(
+        &mut self,
+        _timeout: Duration,
+    ) -> Option<WindowEvent<Self::Window>> {
+        self.events.pop_front()
+    }
+
+    fn handle_event(&mut self, event: WindowEvent<Self::Window>) {
+        if let WindowEvent::ConfigureEvent(event) = event {
+            self.configure_window(
+                event.window,
+                Some(event.size),
+                Some(event.position),
+                event.border,
+            );
+        }
+    }
+
+    fn add_keybind(&mut self, keybind: KeyOrMouseBind) {
+        self.keybinds.push(keybind);
+    }
+
+    fn remove_keybind(&mut self, keybind: &KeyOrMouseBind) {
+        self.keybinds.retain(|kb| kb != keybind);
+    }
+
+    fn focus_window(&self, window: Self::Window) {
+        self.record(Call::Focus(window));
+    }
+
+    fn unfocus_window(&self, window: Self::Window) {
+        self.record(Call::Unfocus(window));
+    }
+
+    fn paint_window_border(&self, _window: Self::Window, _focused: bool) {}
+
+    fn set_window_opacity(&self, _window: Self::Window, _opacity: f32) {}
+
+    fn window_demands_attention(&self, window: Self::Window) -> bool {
+        self.window(window).is_some_and(|w| w.urgent)
+    }
+
+    fn set_window_urgent(&self, window: Self::Window, urgent: bool) {
+        self.windows.borrow_mut().entry(window).or_default().urgent = urgent;
+    }
+
+    fn raise_window(&self, window: Self::Window) {
+        self.record(Call::Raise(window));
+    }
+
+    fn lower_window(&self, window: Self::Window) {
+        self.record(Call::Lower(window));
+    }
+
+    fn hide_window(&self, window: Self::Window) {
+        self.record(Call::Hide(window));
+    }
+
+    fn minimize_window(&self, window: Self::Window) {
+        self.record(Call::Minimize(window));
+    }
+
+    fn restore_window(&self, window: Self::Window) {
+        self.record(Call::Restore(window));
+    }
+
+    fn set_window_maximized(
+        &self,
+        _window: Self::Window,
+        _vert: bool,
+        _horz: bool,
+    ) {
+    }
+
+    fn kill_window(&self, window: Self::Window) {
+        self.record(Call::Kill(window));
+    }
+
+    fn can_close_window(&self, _window: Self::Window) -> bool {
+        true
+    }
+
+    fn ping_window(&self, window: Self::Window) -> bool {
+        self.record(Call::Ping(window));
+        true
+    }
+
+    fn force_kill_window(&self, window: Self::Window) {
+        self.record(Call::ForceKill(window));
+    }
+
+    fn get_parent_window(&self, window: Self::Window) -> Option<Self::Window> {
+        self.window(window).and_then(|w| w.parent)
+    }
+
+    fn configure_window(
+        &self,
+        window: Self::Window,
+        new_size: Option<Size<i32>>,
+        new_pos: Option<Point<i32>>,
+        new_border: Option<i32>,
+    ) {
+        self.record(Call::Configure(window));
+
+        let mut windows = self.windows.borrow_mut();
+        let mock = windows.entry(window).or_default();
+
+        if let Some(size) = new_size {
+            mock.size = size;
+        }
+        if let Some(position) = new_pos {
+            mock.position = position;
+        }
+        if let Some(border) = new_border {
+            mock.border = border;
+        }
+    }
+
+    fn set_frame_extents(&self, window: Self::Window, border: i32) {
+        self.windows
+            .borrow_mut()
+            .entry(window)
+            .or_default()
+            .frame_extents = Some(border);
+    }
+
+    fn notify_geometry(
+        &self,
+        _window: Self::Window,
+        _rect: (Point<i32>, Size<i32>),
+        _border: i32,
+    ) {
+    }
+
+    fn screen_size(&self) -> Size<i32> {
+        self.screen_size
+    }
+
+    fn display_name(&self) -> Option<String> {
+        None
+    }
+
+    fn get_window_size(&self, window: Self::Window) -> Option<Size<i32>> {
+        self.window(window).map(|w| w.size)
+    }
+
+    fn get_window_rect(
+        &self,
+        window: Self::Window,
+    ) -> Option<(Point<i32>, Size<i32>)> {
+        self.window(window).map(|w| (w.position, w.size))
+    }
+
+    fn get_window_name(&self, window: Self::Window) -> Option<String> {
+        self.window(window).and_then(|w| w.name)
+    }
+
+    fn get_window_role(&self, window: Self::Window) -> Option<String> {
+        self.window(window).and_then(|w| w.role)
+    }
+
+    fn get_window_type(&self, window: Self::Window) -> WindowType {
+        self.window(window)
+            .map(|w| w.window_type)
+            .unwrap_or(WindowType::Normal)
+    }
+
+    fn get_window_strut(&self, window: Self::Window) -> Option<Strut> {
+        self.window(window).and_then(|w| w.strut)
+    }
+
+    fn get_window_desktop(&self, window: Self::Window) -> Option<usize> {
+        self.window(window).and_then(|w| w.desktop)
+    }
+
+    fn get_window_pid(&self, window: Self::Window) -> Option<u32> {
+        self.window(window).and_then(|w| w.pid)
+    }
+
+    fn get_window_aspect(&self, window: Self::Window) -> Option<(f32, f32)> {
+        self.window(window).and_then(|w| w.aspect)
+    }
+
+    fn get_window_icon(&self, _window: Self::Window) -> Option<Vec<Icon>> {
+        None
+    }
+
+    fn show_switcher(&mut self, _entries: &[String], _selected: usize) -> bool {
+        true
+    }
+
+    fn hide_switcher(&mut self) {}
+
+    fn grab_server(&self) {}
+
+    fn ungrab_server(&self) {}
+
+    fn flush(&self) {
+        self.flushes.set(self.flushes.get() + 1);
+    }
+
+    fn cleanup(&self) {}
+
+    fn grab_cursor(&self, _shape: CursorShape) {}
+
+    fn ungrab_cursor(&self) {}
+
+    fn move_cursor(
+        &self,
+        _window: Option<Self::Window>,
+        _position: Point<i32>,
+    ) {
+    }
+
+    fn all_windows(&self) -> Option<Vec<Self::Window>> {
+        Some(Vec::new())
+    }
+
+    fn set_active_window_border_color(&mut self, _color_name: &str) {}
+
+    fn set_inactive_window_border_color(&mut self, _color_name: &str) {}
+
+    fn set_inner_border(&mut self, _color_name: &str, _width: i32) {}
+
+    fn set_root_color(&self, _color_name: &str) {}
+
+    fn bell(&self) {
+        self.record(Call::Bell);
+    }
+
+    fn set_layout_name(&self, name: &str) {
+        self.layout_name.replace(Some(name.to_owned()));
+    }
+
+    fn set_work_areas(&self, areas: &[Rect], _viewports: &[Point<i32>]) {
+        self.work_areas.replace(areas.to_vec());
+    }
+}
@@ -1,4 +1,6 @@
 pub mod keycodes;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
 pub mod traits;
 pub mod window_event;
 pub mod xlib;
@@ -7,7 +9,9 @@ pub use traits::*;
 
 pub mod structs {
 
-    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+    #[derive(
+        Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, serde::Deserialize,
+    )]
     pub enum WindowType {
         Splash,
         Dialog,
@@ -18,4 +22,52 @@ pub mod structs {
         Dock,
         Desktop,
     }
+
+    /// a window icon as `(width, height, pixels)`, with the pixels in
+    /// row-major order, packed as ARGB with 8 bits per channel.
+    pub type Icon = (u32, u32, Vec<u32>);
+
+    /// returns the icon with the most pixels.
+    pub fn largest_icon(icons: &[Icon]) -> Option<&Icon> {
+        icons.iter().max_by_key(|(width, height, _)| width * height)
+    }
+
+    /// cursor shown while the pointer is grabbed, or on the root window.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub enum CursorShape {
+        Normal,
+        Move,
+        Resize,
+    }
+
+    /// space reserved at the edges of the screen by a window, e.g. a bar.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+    pub struct Strut {
+        pub left: i32,
+        pub right: i32,
+        pub top: i32,
+        pub bottom: i32,
+    }
+
+    impl Strut {
+        pub fn new(left: i32, right: i32, top: i32, bottom: i32) -> Self {
+            Self {
+                left,
+                right,
+                top,
+                bottom,
+            }
+        }
+
+        /// returns a strut reserving the larger space of `self` and `other` on
+        /// each edge.
+        pub fn max(self, other: Self) -> Self {
+            Self::new(
+                self.left.max(other.left),
+                self.right.max(other.right),
+                self.top.max(other.top),
+                self.bottom.max(other.bottom),
+            )
+        }
+    }
 }
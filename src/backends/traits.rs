@@ -1,16 +1,58 @@
 use super::{
-    structs::WindowType,
+    structs::{CursorShape, Icon, Strut, WindowType},
     window_event::{self, KeyOrMouseBind},
 };
-use crate::util::{Point, Size};
+use crate::util::{Point, Rect, Size};
+use std::time::Duration;
+
+/// settings a backend is built with, see `WindowServerBackend::build_with`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackendConfig {
+    /// name advertised in `_NET_WM_NAME` of the supporting check window.
+    pub wm_name: String,
+    /// name of the cursor shown on the root window, e.g. `left_ptr`.
+    pub cursor: String,
+}
+
+impl Default for BackendConfig {
+    fn default() -> Self {
+        Self {
+            wm_name: "nirgendwm".to_string(),
+            cursor: "left_ptr".to_string(),
+        }
+    }
+}
+
+impl BackendConfig {
+    pub fn with_wm_name(self, wm_name: String) -> Self {
+        Self { wm_name, ..self }
+    }
+
+    pub fn with_cursor(self, cursor: String) -> Self {
+        Self { cursor, ..self }
+    }
+}
 
 pub trait WindowServerBackend {
     type Window;
     //type WindowEvent = super::window_event::WindowEvent<Self::Window>;
 
-    fn build() -> Self;
+    fn build() -> Self
+    where
+        Self: Sized,
+    {
+        Self::build_with(BackendConfig::default())
+    }
+
+    fn build_with(config: BackendConfig) -> Self;
 
     fn next_event(&mut self) -> window_event::WindowEvent<Self::Window>;
+    /// like `next_event`, but returns `None` if no event arrived within
+    /// `timeout` or the wait was interrupted by a signal.
+    fn next_event_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Option<window_event::WindowEvent<Self::Window>>;
     fn handle_event(&mut self, event: window_event::WindowEvent<Self::Window>);
 
     /// adds a keybind to the specified `window`, or globally if `window` is `None`.
@@ -20,9 +62,38 @@ pub trait WindowServerBackend {
 
     fn focus_window(&self, window: Self::Window);
     fn unfocus_window(&self, window: Self::Window);
+    /// sets the border color of `window` to the focused or unfocused color.
+    fn paint_window_border(&self, window: Self::Window, focused: bool);
+    /// sets the opacity of `window` from 0.0 to 1.0 for compositors to apply,
+    /// an opacity of 1.0 removes it.
+    fn set_window_opacity(&self, window: Self::Window, opacity: f32);
+    /// returns `true` if `window` is marked urgent or demands attention.
+    fn window_demands_attention(&self, window: Self::Window) -> bool;
+    /// sets or clears the `_NET_WM_STATE_DEMANDS_ATTENTION` state of `window`.
+    fn set_window_urgent(&self, window: Self::Window, urgent: bool);
     fn raise_window(&self, window: Self::Window);
+    fn lower_window(&self, window: Self::Window);
     fn hide_window(&self, window: Self::Window);
+    /// unmaps `window` and marks it as iconic/hidden.
+    fn minimize_window(&self, window: Self::Window);
+    /// maps a minimized `window` and marks it as normal again.
+    fn restore_window(&self, window: Self::Window);
+    /// marks `window` as maximized in the given axes.
+    fn set_window_maximized(
+        &self,
+        window: Self::Window,
+        vert: bool,
+        horz: bool,
+    );
     fn kill_window(&self, window: Self::Window);
+    /// returns `true` if `window` can be closed gracefully, i.e. it supports
+    /// the `WM_DELETE_WINDOW` protocol.
+    fn can_close_window(&self, window: Self::Window) -> bool;
+    /// sends `window` a `_NET_WM_PING`, returns `false` if it doesn't
+    /// support the protocol. The answer arrives as a `PongEvent`.
+    fn ping_window(&self, window: Self::Window) -> bool;
+    /// kills the client owning `window` without asking it first.
+    fn force_kill_window(&self, window: Self::Window);
     fn get_parent_window(&self, window: Self::Window) -> Option<Self::Window>;
     fn configure_window(
         &self,
@@ -32,12 +103,66 @@ pub trait WindowServerBackend {
         new_border: Option<i32>,
     );
 
+    /// advertises the width of the border drawn around `window` as its
+    /// `_NET_FRAME_EXTENTS`.
+    fn set_frame_extents(&self, window: Self::Window, border: i32);
+
+    /// sends `window` a synthetic `ConfigureNotify` with its final geometry,
+    /// as required by ICCCM after the window manager moved or resized it.
+    fn notify_geometry(
+        &self,
+        window: Self::Window,
+        rect: (Point<i32>, Size<i32>),
+        border: i32,
+    );
+
     fn screen_size(&self) -> Size<i32>;
+    /// name of the display the backend is connected to, e.g. `:0`.
+    fn display_name(&self) -> Option<String>;
     fn get_window_size(&self, window: Self::Window) -> Option<Size<i32>>;
+    /// returns the position of `window` in root coordinates and its size.
+    fn get_window_rect(
+        &self,
+        window: Self::Window,
+    ) -> Option<(Point<i32>, Size<i32>)>;
     fn get_window_name(&self, window: Self::Window) -> Option<String>;
+    /// returns the `WM_WINDOW_ROLE` of `window`, which tells apart windows
+    /// of the same class, e.g. a browser's main window and its pop-ups.
+    fn get_window_role(&self, window: Self::Window) -> Option<String>;
     fn get_window_type(&self, window: Self::Window) -> WindowType;
+    fn get_window_strut(&self, window: Self::Window) -> Option<Strut>;
+    /// returns the virtual screen requested in `_NET_WM_DESKTOP`.
+    fn get_window_desktop(&self, window: Self::Window) -> Option<usize>;
+    /// returns the process id advertised in `_NET_WM_PID`.
+    fn get_window_pid(&self, window: Self::Window) -> Option<u32>;
+    /// returns the minimum and maximum width to height ratio requested with
+    /// `PAspect` in `WM_NORMAL_HINTS`.
+    fn get_window_aspect(&self, window: Self::Window) -> Option<(f32, f32)>;
+    /// returns the icons advertised in `_NET_WM_ICON`.
+    fn get_window_icon(&self, window: Self::Window) -> Option<Vec<Icon>>;
+
+    /// shows a list of `entries` with the entry at `selected` highlighted,
+    /// or updates it if it is already shown. the keyboard is grabbed while
+    /// the list is shown, returns `false` if the list can't be shown or the
+    /// keyboard can't be grabbed.
+    fn show_switcher(&mut self, entries: &[String], selected: usize) -> bool;
+    fn hide_switcher(&mut self);
 
-    fn grab_cursor(&self);
+    /// grabs the server so other clients are not processed until the
+    /// matching `ungrab_server`. grabs nest, only the outermost ungrab
+    /// releases the server.
+    fn grab_server(&self);
+    fn ungrab_server(&self);
+    /// sends all queued requests and waits until the server processed them.
+    fn flush(&self);
+    /// removes the window manager's properties from the root window, releases
+    /// its grabs and windows and flushes, so that another window manager can
+    /// be started cleanly after it exits.
+    fn cleanup(&self);
+
+    /// grabs the pointer, showing the cursor for `shape` until it is
+    /// ungrabbed.
+    fn grab_cursor(&self, shape: CursorShape);
     fn ungrab_cursor(&self);
     fn move_cursor(&self, window: Option<Self::Window>, position: Point<i32>);
 
@@ -45,6 +170,19 @@ pub trait WindowServerBackend {
 
     fn set_active_window_border_color(&mut self, color_name: &str);
     fn set_inactive_window_border_color(&mut self, color_name: &str);
+    /// draws a second border of `width` in `color_name` along the inside of
+    /// window borders, a width of 0 disables it.
+    fn set_inner_border(&mut self, color_name: &str, width: i32);
+    /// sets the background color of the root window.
+    fn set_root_color(&self, color_name: &str);
+    /// rings the bell, e.g. to signal an error.
+    fn bell(&self);
+    /// publishes the name of the current layout, e.g. for status bars.
+    fn set_layout_name(&self, name: &str);
+    /// publishes the area not covered by struts of each desktop in
+    /// `_NET_WORKAREA` and the viewport of each desktop in
+    /// `_NET_DESKTOP_VIEWPORT`.
+    fn set_work_areas(&self, areas: &[Rect], viewports: &[Point<i32>]);
 
     fn resize_window(&self, window: Self::Window, new_size: Size<i32>) {
         self.configure_window(window, Some(new_size), None, None);
@@ -53,4 +191,13 @@ pub trait WindowServerBackend {
     fn move_window(&self, window: Self::Window, new_pos: Point<i32>) {
         self.configure_window(window, None, Some(new_pos), None);
     }
+
+    fn warp_cursor_to_center(&self, window: Self::Window) {
+        if let Some((position, size)) = self.get_window_rect(window) {
+            self.move_cursor(
+                None,
+                position + Point::new(size.width / 2, size.height / 2),
+            );
+        }
+    }
 }
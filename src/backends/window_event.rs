@@ -20,8 +20,16 @@ pub enum WindowEvent<Window> {
     EnterEvent(EnterEvent<Window>),
     ConfigureEvent(ConfigureEvent<Window>),
     FullscreenEvent(FullscreenEvent<Window>), //1 { window: Window, event: 1 },
+    MaximizeEvent(MaximizeEvent<Window>),
+    DesktopChangeEvent(DesktopChangeEvent<Window>),
+    MoveResizeRequestEvent(MoveResizeRequestEvent<Window>),
+    MoveResizeGrabEvent(MoveResizeGrabEvent<Window>),
     WindowNameEvent(WindowNameEvent<Window>),
     WindowTypeChangedEvent(WindowTypeChangedEvent<Window>),
+    UrgencyEvent(UrgencyEvent<Window>),
+    PongEvent(PongEvent<Window>),
+    MinimizeEvent(MinimizeEvent<Window>),
+    ScreenChangeEvent(ScreenChangeEvent),
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -222,6 +230,8 @@ pub struct ConfigureEvent<Window> {
     pub window: Window,
     pub position: Point<i32>,
     pub size: Size<i32>,
+    /// border width, if the window requested one
+    pub border: Option<i32>,
 }
 
 impl<Window> ConfigureEvent<Window> {
@@ -230,8 +240,13 @@ impl<Window> ConfigureEvent<Window> {
             window,
             position,
             size,
+            border: None,
         }
     }
+
+    pub fn with_border(self, border: Option<i32>) -> Self {
+        Self { border, ..self }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -262,6 +277,99 @@ impl<Window> FullscreenEvent<Window> {
     }
 }
 
+#[derive(Debug, Clone)]
+pub enum MaximizeState {
+    On,
+    Off,
+    Toggle,
+}
+
+/// request to (un)maximize `window` vertically and/or horizontally.
+#[derive(Debug, Clone)]
+pub struct MaximizeEvent<Window> {
+    pub window: Window,
+    pub vert: bool,
+    pub horz: bool,
+    pub state: MaximizeState,
+}
+
+impl<Window> MaximizeEvent<Window> {
+    pub fn new(
+        window: Window,
+        vert: bool,
+        horz: bool,
+        state: MaximizeState,
+    ) -> Self {
+        Self {
+            window,
+            vert,
+            horz,
+            state,
+        }
+    }
+}
+
+/// request from a client to move and resize `window`.
+#[derive(Debug, Clone)]
+pub struct MoveResizeRequestEvent<Window> {
+    pub window: Window,
+    pub position: Point<i32>,
+    pub size: Size<i32>,
+}
+
+impl<Window> MoveResizeRequestEvent<Window> {
+    pub fn new(window: Window, position: Point<i32>, size: Size<i32>) -> Self {
+        Self {
+            window,
+            position,
+            size,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveResizeAction {
+    Move,
+    Resize,
+    Cancel,
+}
+
+/// request from a client to start (or cancel) an interactive move or resize
+/// of `window` with the pointer at `cursor_position`.
+#[derive(Debug, Clone)]
+pub struct MoveResizeGrabEvent<Window> {
+    pub window: Window,
+    pub cursor_position: Point<i32>,
+    pub action: MoveResizeAction,
+}
+
+impl<Window> MoveResizeGrabEvent<Window> {
+    pub fn new(
+        window: Window,
+        cursor_position: Point<i32>,
+        action: MoveResizeAction,
+    ) -> Self {
+        Self {
+            window,
+            cursor_position,
+            action,
+        }
+    }
+}
+
+/// request to move `window` to the virtual screen `desktop`.
+#[derive(Debug, Clone)]
+pub struct DesktopChangeEvent<Window> {
+    pub window: Window,
+    pub desktop: usize,
+}
+
+impl<Window> DesktopChangeEvent<Window> {
+    pub fn new(window: Window, desktop: usize) -> Self {
+        Self { window, desktop }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct WindowNameEvent<Window> {
     pub window: Window,
@@ -289,6 +397,56 @@ impl<Window> WindowTypeChangedEvent<Window> {
     }
 }
 
+/// `window` was marked urgent or its urgency was cleared.
+#[derive(Debug, Clone)]
+pub struct UrgencyEvent<Window> {
+    pub window: Window,
+    pub urgent: bool,
+}
+
+impl<Window> UrgencyEvent<Window> {
+    pub fn new(window: Window, urgent: bool) -> Self {
+        Self { window, urgent }
+    }
+}
+
+/// the screen was resized, e.g. because a monitor was plugged in.
+#[derive(Debug, Clone)]
+pub struct ScreenChangeEvent {
+    pub size: Size<i32>,
+}
+
+impl ScreenChangeEvent {
+    pub fn new(size: Size<i32>) -> Self {
+        Self { size }
+    }
+}
+
+/// `window` answered a `_NET_WM_PING` and is therefore still responsive.
+#[derive(Debug, Clone)]
+pub struct PongEvent<Window> {
+    pub window: Window,
+}
+
+impl<Window> PongEvent<Window> {
+    pub fn new(window: Window) -> Self {
+        Self { window }
+    }
+}
+
+/// `window` asked to be minimized, e.g. with an ICCCM `WM_CHANGE_STATE`
+/// message.
+#[derive(Debug, Clone)]
+pub struct MinimizeEvent<Window> {
+    pub window: Window,
+}
+
+impl<Window> MinimizeEvent<Window> {
+    pub fn new(window: Window) -> Self {
+        Self { window }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct KeyBind {
     pub key: VirtualKeyCode,
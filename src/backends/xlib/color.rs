@@ -13,16 +13,23 @@ impl XftColor {
         self.inner.pixel
     }
 
+    pub fn as_ptr(&self) -> *const xft::XftColor {
+        &self.inner
+    }
+
     #[allow(dead_code)]
     pub fn color(&self) -> x11::xrender::XRenderColor {
         self.inner.color
     }
 
+    /// allocates a color from `color_name`, which can be an X11 color name
+    /// (e.g. `steelblue`), `#rrggbb`, or `#rrggbbaa` (the alpha is ignored).
     pub fn new(
         dpy: Display,
         screen: i32,
-        mut color_name: String,
+        color_name: String,
     ) -> Result<Self, std::io::Error> {
+        let mut color_name = strip_alpha(color_name);
         color_name.push('\0');
         let mut color = MaybeUninit::<xft::XftColor>::zeroed();
 
@@ -44,3 +51,28 @@ impl XftColor {
         ))
     }
 }
+
+/// strips the alpha channel from `#rrggbbaa` colors, since window borders
+/// can't be translucent.
+fn strip_alpha(mut color_name: String) -> String {
+    if color_name.starts_with('#')
+        && color_name.len() == 9
+        && color_name[1..].chars().all(|c| c.is_ascii_hexdigit())
+    {
+        color_name.truncate(7);
+    }
+
+    color_name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_alpha_only_from_rgba() {
+        assert_eq!(strip_alpha("#11223344".to_owned()), "#112233");
+        assert_eq!(strip_alpha("#112233".to_owned()), "#112233");
+        assert_eq!(strip_alpha("steelblue".to_owned()), "steelblue");
+    }
+}
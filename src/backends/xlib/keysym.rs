@@ -14,8 +14,9 @@ pub fn xev_to_mouse_button(
         5 => Some(MouseButton::ScrollDown),
         6 => Some(MouseButton::ScrollLeft),
         7 => Some(MouseButton::ScrollRight),
-        8 => Some(MouseButton::Forward),
-        9 => Some(MouseButton::Backward),
+        // the thumb buttons, button 8 is "back" by convention
+        8 => Some(MouseButton::Backward),
+        9 => Some(MouseButton::Forward),
         _ => None,
     }
 }
@@ -29,8 +30,8 @@ pub fn mouse_button_to_xbutton(button: MouseButton) -> i32 {
         MouseButton::ScrollDown => 5,
         MouseButton::ScrollLeft => 6,
         MouseButton::ScrollRight => 7,
-        MouseButton::Forward => 8,
-        MouseButton::Backward => 9,
+        MouseButton::Backward => 8,
+        MouseButton::Forward => 9,
     }
 }
 
@@ -1080,6 +1081,15 @@ pub fn keysym_to_virtual_keycode(keysym: u32) -> Option<VirtualKeyCode> {
         x11::keysym::XF86XK_Copy => VirtualKeyCode::Copy,
         x11::keysym::XF86XK_Paste => VirtualKeyCode::Paste,
         x11::keysym::XF86XK_Cut => VirtualKeyCode::Cut,
+        x11::keysym::XF86XK_AudioRaiseVolume => VirtualKeyCode::VolumeUp,
+        x11::keysym::XF86XK_AudioLowerVolume => VirtualKeyCode::VolumeDown,
+        x11::keysym::XF86XK_AudioMute => VirtualKeyCode::Mute,
+        x11::keysym::XF86XK_AudioPlay => VirtualKeyCode::PlayPause,
+        x11::keysym::XF86XK_AudioStop => VirtualKeyCode::MediaStop,
+        x11::keysym::XF86XK_AudioPrev => VirtualKeyCode::PrevTrack,
+        x11::keysym::XF86XK_AudioNext => VirtualKeyCode::NextTrack,
+        x11::keysym::XF86XK_MonBrightnessUp => VirtualKeyCode::BrightnessUp,
+        x11::keysym::XF86XK_MonBrightnessDown => VirtualKeyCode::BrightnessDown,
         _ => return None,
     })
 }
@@ -2085,6 +2095,15 @@ pub fn virtual_keycode_to_keysym(keycode: VirtualKeyCode) -> Option<u32> {
         VirtualKeyCode::Copy => x11::keysym::XF86XK_Copy,
         VirtualKeyCode::Paste => x11::keysym::XF86XK_Paste,
         VirtualKeyCode::Cut => x11::keysym::XF86XK_Cut,
+        VirtualKeyCode::VolumeUp => x11::keysym::XF86XK_AudioRaiseVolume,
+        VirtualKeyCode::VolumeDown => x11::keysym::XF86XK_AudioLowerVolume,
+        VirtualKeyCode::Mute => x11::keysym::XF86XK_AudioMute,
+        VirtualKeyCode::PlayPause => x11::keysym::XF86XK_AudioPlay,
+        VirtualKeyCode::MediaStop => x11::keysym::XF86XK_AudioStop,
+        VirtualKeyCode::PrevTrack => x11::keysym::XF86XK_AudioPrev,
+        VirtualKeyCode::NextTrack => x11::keysym::XF86XK_AudioNext,
+        VirtualKeyCode::BrightnessUp => x11::keysym::XF86XK_MonBrightnessUp,
+        VirtualKeyCode::BrightnessDown => x11::keysym::XF86XK_MonBrightnessDown,
         _ => return None,
     })
 }
@@ -2105,4 +2124,62 @@ mod tests {
         assert_eq!(keysym2, keysym);
         assert_eq!(&x11::keysym::XK_W, keysym.as_ref());
     }
+
+    #[test]
+    fn function_keypad_and_media_keys_round_trip() {
+        use VirtualKeyCode::*;
+
+        let keycodes = [
+            F1,
+            F2,
+            F3,
+            F4,
+            F5,
+            F6,
+            F7,
+            F8,
+            F9,
+            F10,
+            F11,
+            F12,
+            F13,
+            F14,
+            F15,
+            F16,
+            F17,
+            F18,
+            F19,
+            F20,
+            F21,
+            F22,
+            F23,
+            F24,
+            Numpad0,
+            Numpad1,
+            Numpad2,
+            Numpad3,
+            Numpad4,
+            Numpad5,
+            Numpad6,
+            Numpad7,
+            Numpad8,
+            Numpad9,
+            VolumeUp,
+            VolumeDown,
+            Mute,
+            PlayPause,
+            MediaStop,
+            PrevTrack,
+            NextTrack,
+            BrightnessUp,
+            BrightnessDown,
+        ];
+
+        for keycode in keycodes {
+            let keysym = virtual_keycode_to_keysym(keycode)
+                .unwrap_or_else(|| panic!("no keysym for {:?}", keycode));
+
+            assert_eq!(keysym_to_virtual_keycode(keysym), Some(keycode));
+        }
+    }
 }
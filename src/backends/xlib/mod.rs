@@ -1,10 +1,19 @@
-use log::{debug, error, warn};
+use log::{debug, error, trace, warn};
 use num_traits::Zero;
-use std::{convert::TryFrom, ptr::NonNull, rc::Rc};
+use std::{
+    cell::Cell,
+    convert::TryFrom,
+    os::raw::{c_int, c_long},
+    ptr::NonNull,
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
 use thiserror::Error;
 
-use x11::xlib::{self, Atom, Success, Window, XEvent, XKeyEvent, XA_WINDOW};
+use x11::xlib::{
+    self, Atom, Success, Window, XEvent, XKeyEvent, XA_CARDINAL, XA_WINDOW,
+};
 
 use crate::backends::{
     keycodes::KeyOrButton, xlib::keysym::mouse_button_to_xbutton,
@@ -17,21 +26,27 @@ use self::{
         keysym_to_virtual_keycode, virtual_keycode_to_keysym,
         xev_to_mouse_button, XKeySym,
     },
-    wmh::{ICCCMAtom, ICCCMAtoms},
+    wmh::{ICCCMAtom, ICCCMAtoms, WmState},
 };
 
 use super::{
     keycodes::VirtualKeyCode,
-    structs::WindowType,
+    structs::{CursorShape, Icon, Strut, WindowType},
+    traits::BackendConfig,
     window_event::{
-        ButtonEvent, ConfigureEvent, DestroyEvent, EnterEvent, FullscreenEvent,
-        FullscreenState, KeyEvent, KeyOrMouseBind, KeyState, MapEvent,
-        ModifierState, MotionEvent, UnmapEvent, WindowEvent, WindowNameEvent,
-        WindowTypeChangedEvent,
+        ButtonEvent, ConfigureEvent, DesktopChangeEvent, DestroyEvent,
+        EnterEvent, FullscreenEvent, FullscreenState, KeyEvent, KeyOrMouseBind,
+        KeyState, MapEvent, MaximizeEvent, MaximizeState, MinimizeEvent,
+        ModifierState, MotionEvent, MoveResizeAction, MoveResizeGrabEvent,
+        MoveResizeRequestEvent, PongEvent, ScreenChangeEvent, UnmapEvent,
+        UrgencyEvent, WindowEvent, WindowNameEvent, WindowTypeChangedEvent,
     },
     WindowServerBackend,
 };
-use crate::util::{Point, Size};
+use crate::{
+    ui::Switcher,
+    util::{Point, Rect, Size},
+};
 
 pub mod color;
 pub mod keysym;
@@ -109,12 +124,14 @@ impl From<u8> for XlibError {
 pub mod wmh {
     use std::{borrow::Borrow, ffi::CString, ops::Index};
 
-    use strum::{EnumCount, EnumIter};
+    use strum::{EnumCount, EnumIter, FromRepr};
     use x11::xlib::Atom;
 
     use super::{connection::XLibConnection, Display};
 
-    #[derive(Debug, PartialEq, Eq, EnumIter, EnumCount, Clone, Copy)]
+    #[derive(
+        Debug, PartialEq, Eq, EnumIter, EnumCount, Clone, Copy, FromRepr,
+    )]
     pub enum ICCCMAtom {
         WmName,
         WmProtocols,
@@ -123,9 +140,20 @@ pub mod wmh {
         WmTakeFocus,
         WmState,
         WmTransientFor,
+        WmWindowRole,
+        WmChangeState,
         Utf8String,
     }
 
+    /// values of the ICCCM `WM_STATE` property
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    #[allow(dead_code)]
+    pub enum WmState {
+        Withdrawn = 0,
+        Normal = 1,
+        Iconic = 3,
+    }
+
     #[derive(Debug, Clone)]
     pub struct ICCCMAtoms {
         inner: Vec<Atom>,
@@ -146,6 +174,13 @@ pub mod wmh {
             ICCCMAtom::try_get_atoms(con.borrow().display())
                 .map(|atoms| Self { inner: atoms })
         }
+
+        pub fn reverse_lookup(&self, atom: Atom) -> Option<ICCCMAtom> {
+            self.inner
+                .iter()
+                .position(|a| *a == atom)
+                .and_then(ICCCMAtom::from_repr)
+        }
     }
 
     impl ICCCMAtom {
@@ -187,6 +222,8 @@ pub mod wmh {
                 ICCCMAtom::WmTakeFocus => "WM_TAKE_FOCUS",
                 ICCCMAtom::WmState => "WM_STATE",
                 ICCCMAtom::WmTransientFor => "WM_TRANSIENT_FOR",
+                ICCCMAtom::WmWindowRole => "WM_WINDOW_ROLE",
+                ICCCMAtom::WmChangeState => "WM_CHANGE_STATE",
                 ICCCMAtom::Utf8String => "UTF8_STRING",
             }
         }
@@ -276,6 +313,7 @@ pub mod ewmh {
         NetWmActionFullscreen,
         NetWmActionChangeDesktop,
         NetWmActionClose,
+        NetWmWindowOpacity,
     }
 
     #[derive(Debug, Clone)]
@@ -316,6 +354,15 @@ pub mod ewmh {
                 self[EWMHAtom::NetWmName],
                 self[EWMHAtom::NetClientList],
                 self[EWMHAtom::NetWmStateFullscreen],
+                self[EWMHAtom::NetWmStateHidden],
+                self[EWMHAtom::NetWmStateMaximizedVert],
+                self[EWMHAtom::NetWmStateMaximizedHorz],
+                self[EWMHAtom::NetMoveresizeWindow],
+                self[EWMHAtom::NetWmMoveresize],
+                self[EWMHAtom::NetFrameExtents],
+                self[EWMHAtom::NetWmStateDemandsAttention],
+                self[EWMHAtom::NetWorkarea],
+                self[EWMHAtom::NetDesktopViewport],
             ]
             .to_vec();
 
@@ -455,6 +502,7 @@ pub mod ewmh {
                     "_NET_WM_ACTION_CHANGE_DESKTOP"
                 }
                 EWMHAtom::NetWmActionClose => "_NET_WM_ACTION_CLOSE",
+                EWMHAtom::NetWmWindowOpacity => "_NET_WM_WINDOW_OPACITY",
             }
         }
     }
@@ -474,7 +522,7 @@ pub mod ewmh {
 
 pub mod connection {
     use std::{
-        ffi::CString,
+        ffi::{CStr, CString},
         mem::size_of,
         os::raw::{c_char, c_long},
     };
@@ -484,6 +532,41 @@ pub mod connection {
 
     use super::{xpointer::XPointer, Display};
 
+    /// length in 32 bit units read from a property at once.
+    const PROPERTY_CHUNK: c_long = 4096 / 4;
+
+    /// part of a property returned by a single `XGetWindowProperty`.
+    pub(super) struct PropertyChunk {
+        pub(super) data: Vec<u8>,
+        /// length of the data as stored by the server, where 32 bit items
+        /// take 4 bytes rather than a `c_long`
+        pub(super) wire_len: usize,
+        pub(super) bytes_after: usize,
+    }
+
+    /// reads a whole property with `read`, which returns the chunk starting
+    /// at the given offset in 32 bit units. a single read returns at most
+    /// `PROPERTY_CHUNK` units, so large properties like `_NET_WM_ICON` take
+    /// several.
+    pub(super) fn read_property_chunks<F>(mut read: F) -> Option<Vec<u8>>
+    where
+        F: FnMut(c_long) -> Option<PropertyChunk>,
+    {
+        let mut data = Vec::new();
+        let mut offset = 0;
+
+        loop {
+            let chunk = read(offset)?;
+            data.extend_from_slice(&chunk.data);
+
+            if chunk.bytes_after == 0 || chunk.wire_len == 0 {
+                return Some(data);
+            }
+
+            offset += (chunk.wire_len / 4) as c_long;
+        }
+    }
+
     pub struct XLibConnection {
         display: Display,
         root: Window,
@@ -544,12 +627,34 @@ pub mod connection {
             self.screen
         }
 
+        /// returns the atom called `name`, creating it if it doesn't exist.
+        pub fn intern_atom(&self, name: &str) -> Option<Atom> {
+            let name = CString::new(name).ok()?;
+
+            match unsafe { xlib::XInternAtom(self.dpy(), name.as_ptr(), 0) } {
+                0 => None,
+                atom => Some(atom),
+            }
+        }
+
         pub fn get_window_property(
             &self,
             window: Window,
             atom: Atom,
             atom_type: Atom,
         ) -> Option<Vec<u8>> {
+            read_property_chunks(|offset| {
+                self.get_window_property_chunk(window, atom, atom_type, offset)
+            })
+        }
+
+        fn get_window_property_chunk(
+            &self,
+            window: Window,
+            atom: Atom,
+            atom_type: Atom,
+            offset: c_long,
+        ) -> Option<PropertyChunk> {
             let mut format_returned = 0;
             let mut items_returned = 0;
             let mut bytes_after_return = 0;
@@ -561,8 +666,8 @@ pub mod connection {
                         self.dpy(),
                         window,
                         atom,
-                        0,
-                        4096 / 4,
+                        offset,
+                        PROPERTY_CHUNK,
                         0,
                         atom_type,
                         &mut type_returned,
@@ -573,14 +678,23 @@ pub mod connection {
                     ) == i32::from(xlib::Success)
                 });
 
-            success.then(|| ptr).flatten().map(|ptr| {
-                unsafe {
+            // xlib returns 32 bit items as `c_long`s
+            let item_size = match format_returned {
+                32 => size_of::<c_long>(),
+                format => format as usize / 8,
+            };
+
+            success.then(|| ptr).flatten().map(|ptr| PropertyChunk {
+                data: unsafe {
                     std::slice::from_raw_parts(
                         ptr.as_ptr(),
-                        items_returned as usize * format_returned as usize,
+                        items_returned as usize * item_size,
                     )
                 }
-                .to_vec()
+                .to_vec(),
+                wire_len: items_returned as usize * format_returned as usize
+                    / 8,
+                bytes_after: bytes_after_return as usize,
             })
         }
 
@@ -599,6 +713,9 @@ pub mod connection {
                 })
         }
 
+        /// reads a text property and decodes it to UTF-8, handling the
+        /// `STRING`, `UTF8_STRING` and `COMPOUND_TEXT` encodings.
+        /// multiple strings in the property are joined with a space.
         pub fn get_text_property(
             &self,
             window: Window,
@@ -615,13 +732,52 @@ pub mod connection {
                     &mut text_prop,
                     atom,
                 ) == 0
+                    || text_prop.value.is_null()
                 {
                     return None;
                 }
 
-                CString::from_raw(text_prop.value.cast::<c_char>())
-                    .into_string()
-                    .ok()
+                let text = self.text_property_to_string(&text_prop);
+                xlib::XFree(text_prop.value.cast());
+
+                text
+            }
+        }
+
+        unsafe fn text_property_to_string(
+            &self,
+            text_prop: &xlib::XTextProperty,
+        ) -> Option<String> {
+            let mut list: *mut *mut c_char = std::ptr::null_mut();
+            let mut count = 0;
+
+            if xlib::Xutf8TextPropertyToTextList(
+                self.dpy(),
+                text_prop,
+                &mut list,
+                &mut count,
+            ) >= i32::from(xlib::Success)
+                && !list.is_null()
+            {
+                let strings = std::slice::from_raw_parts(list, count as usize)
+                    .iter()
+                    .map(|&ptr| CStr::from_ptr(ptr).to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+
+                xlib::XFreeStringList(list);
+
+                Some(strings)
+            } else {
+                // conversion failed, fall back to interpreting the raw bytes
+                (text_prop.nitems > 0).then(|| {
+                    String::from_utf8_lossy(std::slice::from_raw_parts(
+                        text_prop.value,
+                        text_prop.nitems as usize,
+                    ))
+                    .trim_end_matches('\0')
+                    .to_owned()
+                })
             }
         }
 
@@ -721,10 +877,91 @@ pub struct XLib {
     active_border_color: Option<color::XftColor>,
     inactive_border_color: Option<color::XftColor>,
     wm_window: Window,
+    switcher: Option<Switcher>,
+    /// nesting depth of `grab_server` calls
+    server_grabs: Cell<u32>,
+    /// `_PARTWM_LAYOUT`, see `set_layout_name`
+    layout_atom: Option<Atom>,
+    /// color and width of the accent drawn along the inside of window
+    /// borders, see `set_inner_border`
+    inner_border: Option<(color::XftColor, i32)>,
+    /// last window passed to `focus_window`, its border is repainted in the
+    /// focused colors when it is resized
+    active_window: Cell<Option<Window>>,
+    /// name advertised on the supporting check window
+    wm_name: String,
+    /// cursors of the root window and of move and resize grabs, indexed by
+    /// `CursorShape`
+    cursors: [xlib::Cursor; 3],
+}
+
+/// root window property holding the name of the current layout as a UTF-8
+/// string, e.g. `MasterStack`.
+const LAYOUT_ATOM: &str = "_PARTWM_LAYOUT";
+
+/// returns the name of the core X event type `event_type`, for logging.
+fn xevent_type_name(event_type: c_int) -> String {
+    let name = match event_type {
+        xlib::KeyPress => "KeyPress",
+        xlib::KeyRelease => "KeyRelease",
+        xlib::ButtonPress => "ButtonPress",
+        xlib::ButtonRelease => "ButtonRelease",
+        xlib::MotionNotify => "MotionNotify",
+        xlib::EnterNotify => "EnterNotify",
+        xlib::LeaveNotify => "LeaveNotify",
+        xlib::FocusIn => "FocusIn",
+        xlib::FocusOut => "FocusOut",
+        xlib::KeymapNotify => "KeymapNotify",
+        xlib::Expose => "Expose",
+        xlib::GraphicsExpose => "GraphicsExpose",
+        xlib::NoExpose => "NoExpose",
+        xlib::VisibilityNotify => "VisibilityNotify",
+        xlib::CreateNotify => "CreateNotify",
+        xlib::DestroyNotify => "DestroyNotify",
+        xlib::UnmapNotify => "UnmapNotify",
+        xlib::MapNotify => "MapNotify",
+        xlib::MapRequest => "MapRequest",
+        xlib::ReparentNotify => "ReparentNotify",
+        xlib::ConfigureNotify => "ConfigureNotify",
+        xlib::ConfigureRequest => "ConfigureRequest",
+        xlib::GravityNotify => "GravityNotify",
+        xlib::ResizeRequest => "ResizeRequest",
+        xlib::CirculateNotify => "CirculateNotify",
+        xlib::CirculateRequest => "CirculateRequest",
+        xlib::PropertyNotify => "PropertyNotify",
+        xlib::SelectionClear => "SelectionClear",
+        xlib::SelectionRequest => "SelectionRequest",
+        xlib::SelectionNotify => "SelectionNotify",
+        xlib::ColormapNotify => "ColormapNotify",
+        xlib::ClientMessage => "ClientMessage",
+        xlib::MappingNotify => "MappingNotify",
+        xlib::GenericEvent => "GenericEvent",
+        // extension events are numbered above the core events
+        _ => return format!("extension event {}", event_type),
+    };
+
+    name.to_owned()
+}
+
+/// returns the glyph of the cursor called `name` in the X cursor font, see
+/// `X11/cursorfont.h`.
+fn font_cursor_glyph(name: &str) -> Option<u32> {
+    match name {
+        "X_cursor" => Some(0),
+        "arrow" => Some(2),
+        "bottom_right_corner" => Some(14),
+        "crosshair" => Some(34),
+        "fleur" => Some(52),
+        "hand2" => Some(60),
+        "left_ptr" => Some(68),
+        "top_left_arrow" => Some(132),
+        "xterm" => Some(152),
+        _ => None,
+    }
 }
 
 impl XLib {
-    fn new() -> Self {
+    fn new(config: BackendConfig) -> Self {
         let con =
             Rc::new(XLibConnection::new().expect("failed to open x display"));
 
@@ -736,6 +973,21 @@ impl XLib {
             keybinds: Vec::new(),
             active_border_color: None,
             inactive_border_color: None,
+            switcher: None,
+            server_grabs: Cell::new(0),
+            layout_atom: con.intern_atom(LAYOUT_ATOM),
+            inner_border: None,
+            active_window: Cell::new(None),
+            cursors: [
+                font_cursor_glyph(&config.cursor).unwrap_or_else(|| {
+                    warn!("unknown cursor {:?}, using left_ptr", config.cursor);
+                    font_cursor_glyph("left_ptr").unwrap()
+                }),
+                font_cursor_glyph("fleur").unwrap(),
+                font_cursor_glyph("bottom_right_corner").unwrap(),
+            ]
+            .map(|glyph| unsafe { xlib::XCreateFontCursor(con.dpy(), glyph) }),
+            wm_name: config.wm_name,
             wm_window: unsafe {
                 xlib::XCreateSimpleWindow(
                     con.dpy(),
@@ -780,12 +1032,23 @@ impl XLib {
         xlib::XSetErrorHandler(Some(xlib_error_handler));
         xlib::XSync(self.dpy(), 0);
 
+        xlib::XDefineCursor(
+            self.dpy(),
+            self.connection.root(),
+            self.cursors[CursorShape::Normal as usize],
+        );
+
         self.ewmh_atoms.set_supported_atoms(self.connection.clone());
         self.connection.delete_property(
             self.connection.root(),
             self.ewmh_atoms[EWMHAtom::NetClientList],
         );
 
+        // EWMH requires `_NET_SUPPORTING_WM_CHECK` on both the root and the
+        // check window to point to the check window, which in turn carries
+        // the window manager's name in `_NET_WM_NAME`. tools like
+        // `wmctrl -m` compare both to tell a running window manager from a
+        // stale property left behind by one that died.
         self.connection.change_property_long(
             self.wm_window,
             self.ewmh_atoms[EWMHAtom::NetSupportingWmCheck],
@@ -807,7 +1070,7 @@ impl XLib {
             self.ewmh_atoms[EWMHAtom::NetWmName],
             self.atoms[ICCCMAtom::Utf8String],
             PropMode::Replace,
-            "nirgendwm".as_bytes(),
+            self.wm_name.as_bytes(),
         );
     }
 
@@ -824,6 +1087,8 @@ impl XLib {
             event.assume_init()
         };
 
+        let event = self.squash_motion_events(event);
+
         // match event.get_type() {
         //     xlib::KeyPress | xlib::KeyRelease => {
         //         self.update_modifier_state(AsRef::<xlib::XKeyEvent>::as_ref(
@@ -836,6 +1101,35 @@ impl XLib {
         event
     }
 
+    /// skips over motion events queued right after `event` for the same
+    /// window and returns the last of them, so dragging a window only
+    /// handles the latest cursor position instead of flooding the server
+    /// with a configure request for every motion event.
+    fn squash_motion_events(&self, mut event: XEvent) -> XEvent {
+        if event.get_type() != xlib::MotionNotify {
+            return event;
+        }
+
+        // only consecutive events are squashed to keep the order of events
+        unsafe {
+            while xlib::XPending(self.dpy()) > 0 {
+                let mut next = std::mem::MaybeUninit::<xlib::XEvent>::zeroed();
+                xlib::XPeekEvent(self.dpy(), next.as_mut_ptr());
+                let next = next.assume_init();
+
+                if next.get_type() != xlib::MotionNotify
+                    || next.motion.window != event.motion.window
+                {
+                    break;
+                }
+
+                xlib::XNextEvent(self.dpy(), &mut event);
+            }
+        }
+
+        event
+    }
+
     fn xevent_to_window_event(&self, event: XEvent) -> Option<XLibWindowEvent> {
         match event.get_type() {
             xlib::MapRequest => {
@@ -852,11 +1146,17 @@ impl XLib {
             }
             xlib::ConfigureRequest => {
                 let ev = unsafe { &event.configure_request };
-                Some(XLibWindowEvent::ConfigureEvent(ConfigureEvent {
-                    window: ev.window,
-                    position: (ev.x, ev.y).into(),
-                    size: (ev.width, ev.height).into(),
-                }))
+                Some(XLibWindowEvent::ConfigureEvent(
+                    ConfigureEvent::new(
+                        ev.window,
+                        (ev.x, ev.y).into(),
+                        (ev.width, ev.height).into(),
+                    )
+                    .with_border(
+                        (ev.value_mask & xlib::CWBorderWidth as u64 != 0)
+                            .then_some(ev.border_width),
+                    ),
+                ))
             }
             xlib::EnterNotify => {
                 let ev = unsafe { &event.crossing };
@@ -864,6 +1164,17 @@ impl XLib {
                     window: ev.window,
                 }))
             }
+            // RandR resizes the root window when monitors are added,
+            // removed or reconfigured
+            xlib::ConfigureNotify
+                if unsafe { event.configure.window }
+                    == self.connection.root() =>
+            {
+                let ev = unsafe { &event.configure };
+                Some(XLibWindowEvent::ScreenChangeEvent(
+                    ScreenChangeEvent::new((ev.width, ev.height).into()),
+                ))
+            }
             xlib::DestroyNotify => {
                 let ev = unsafe { &event.destroy_window };
                 Some(XLibWindowEvent::DestroyEvent(DestroyEvent {
@@ -881,7 +1192,8 @@ impl XLib {
             // XButtonReleasedEvent or XButtonPressedEvent
             xlib::ButtonPress | xlib::ButtonRelease => {
                 let ev = unsafe { &event.button };
-                let keycode = xev_to_mouse_button(ev).unwrap();
+                // buttons past 9 have no `MouseButton`
+                let keycode = xev_to_mouse_button(ev)?;
                 let state = if ev.type_ == xlib::ButtonPress {
                     KeyState::Pressed
                 } else {
@@ -916,6 +1228,21 @@ impl XLib {
                     ))
                 })
             }
+            xlib::Expose => {
+                let ev = unsafe { &event.expose };
+
+                if let Some(switcher) = self
+                    .switcher
+                    .as_ref()
+                    .filter(|switcher| switcher.window() == ev.window)
+                {
+                    if ev.count == 0 {
+                        switcher.draw();
+                    }
+                }
+
+                None
+            }
             xlib::PropertyNotify => {
                 let ev = unsafe { &event.property };
 
@@ -939,6 +1266,12 @@ impl XLib {
                             ),
                         ))
                     }
+                    xlib::XA_WM_HINTS => {
+                        Some(XLibWindowEvent::UrgencyEvent(UrgencyEvent::new(
+                            ev.window,
+                            self.window_demands_attention(ev.window),
+                        )))
+                    }
                     _ => None,
                 }
             }
@@ -951,38 +1284,152 @@ impl XLib {
                             == self.ewmh_atoms[EWMHAtom::NetWmState] =>
                     {
                         let data = ev.data.as_longs();
-                        if data[1] as u64
-                            == self.ewmh_atoms[EWMHAtom::NetWmStateFullscreen]
-                            || data[2] as u64
-                                == self.ewmh_atoms
-                                    [EWMHAtom::NetWmStateFullscreen]
-                        {
-                            debug!("fullscreen event");
-                            Some(XLibWindowEvent::FullscreenEvent(
-                                FullscreenEvent::new(
+
+                        // _NET_WM_STATE_REMOVE (0), _NET_WM_STATE_ADD (1) or
+                        // _NET_WM_STATE_TOGGLE (2) of up to two states, the
+                        // first state this window manager acts on is handled
+                        let action = data[0];
+                        let states = [data[1], data[2]]
+                            .iter()
+                            .filter_map(|&atom| {
+                                self.ewmh_atoms.reverse_lookup(atom as Atom)
+                            })
+                            .collect::<Vec<_>>();
+
+                        states.iter().find_map(|&state| {
+                            self.net_wm_state_event(
+                                ev.window, state, &states, action,
+                            )
+                        })
+                    }
+                    message_type
+                        if message_type
+                            == self.ewmh_atoms[EWMHAtom::NetWmDesktop] =>
+                    {
+                        let desktop = ev.data.get_long(0) as u32;
+
+                        // 0xFFFFFFFF requests the window on all desktops
+                        (desktop != u32::MAX).then(|| {
+                            XLibWindowEvent::DesktopChangeEvent(
+                                DesktopChangeEvent::new(
                                     ev.window,
-                                    match data[0] /* as u64 */ {
-                                        0 => FullscreenState::Off,
-                                        1 => FullscreenState::On,
-                                        2 => FullscreenState::Toggle,
-                                        _ => {
-                                            unreachable!()
-                                        }
-                                    },
+                                    desktop as usize,
                                 ),
-                            ))
-                        } else {
-                            None
-                        }
+                            )
+                        })
+                    }
+                    message_type
+                        if message_type
+                            == self.ewmh_atoms
+                                [EWMHAtom::NetMoveresizeWindow] =>
+                    {
+                        let flags = ev.data.get_long(0);
+                        let (position, size) =
+                            self.get_window_rect(ev.window)?;
+
+                        // bits 8 through 11 indicate which of x, y, width and
+                        // height are present; missing values are kept.
+                        let value_or = |bit: i64, idx: usize, current: i32| {
+                            if flags & (1 << bit) != 0 {
+                                ev.data.get_long(idx) as i32
+                            } else {
+                                current
+                            }
+                        };
+
+                        Some(XLibWindowEvent::MoveResizeRequestEvent(
+                            MoveResizeRequestEvent::new(
+                                ev.window,
+                                Point::new(
+                                    value_or(8, 1, position.x),
+                                    value_or(9, 2, position.y),
+                                ),
+                                Size::new(
+                                    value_or(10, 3, size.width),
+                                    value_or(11, 4, size.height),
+                                ),
+                            ),
+                        ))
+                    }
+                    message_type
+                        if message_type
+                            == self.ewmh_atoms[EWMHAtom::NetWmMoveresize] =>
+                    {
+                        let cursor_position = Point::new(
+                            ev.data.get_long(0) as i32,
+                            ev.data.get_long(1) as i32,
+                        );
+
+                        // _NET_WM_MOVERESIZE_SIZE_* (0-7, 9 for keyboard),
+                        // _NET_WM_MOVERESIZE_MOVE (8, 10 for keyboard) and
+                        // _NET_WM_MOVERESIZE_CANCEL (11)
+                        let action = match ev.data.get_long(2) {
+                            0..=7 | 9 => Some(MoveResizeAction::Resize),
+                            8 | 10 => Some(MoveResizeAction::Move),
+                            11 => Some(MoveResizeAction::Cancel),
+                            _ => None,
+                        }?;
+
+                        Some(XLibWindowEvent::MoveResizeGrabEvent(
+                            MoveResizeGrabEvent::new(
+                                ev.window,
+                                cursor_position,
+                                action,
+                            ),
+                        ))
+                    }
+                    // clients answer `_NET_WM_PING` by sending it back to the
+                    // root window with the pinged window in the third field
+                    message_type
+                        if message_type
+                            == self.atoms[ICCCMAtom::WmProtocols]
+                            && ev.data.get_long(0) as Atom
+                                == self.ewmh_atoms[EWMHAtom::NetWmPing] =>
+                    {
+                        Some(XLibWindowEvent::PongEvent(PongEvent::new(
+                            ev.data.get_long(2) as Window,
+                        )))
+                    }
+                    // ICCCM 4.1.4: clients ask to be iconified by sending
+                    // `WM_CHANGE_STATE` with `IconicState`
+                    message_type
+                        if message_type
+                            == self.atoms[ICCCMAtom::WmChangeState]
+                            && ev.data.get_long(0)
+                                == WmState::Iconic as i64 =>
+                    {
+                        Some(XLibWindowEvent::MinimizeEvent(
+                            MinimizeEvent::new(ev.window),
+                        ))
+                    }
+                    message_type => {
+                        trace!(
+                            "unhandled client message {} for {}",
+                            self.atom_name(message_type),
+                            ev.window
+                        );
+                        None
                     }
-                    _ => None,
                 }
             }
-            _ => None,
+            event_type => {
+                trace!("unhandled event {}", xevent_type_name(event_type));
+                None
+            }
         }
     }
 
-    #[allow(dead_code)]
+    /// returns the name of `atom` if it is one of the interned ICCCM or EWMH
+    /// atoms, for logging.
+    fn atom_name(&self, atom: Atom) -> String {
+        self.ewmh_atoms
+            .reverse_lookup(atom)
+            .map(<&str>::from)
+            .or_else(|| self.atoms.reverse_lookup(atom).map(<&str>::from))
+            .map(str::to_owned)
+            .unwrap_or_else(|| format!("atom {}", atom))
+    }
+
     fn get_window_attributes(
         &self,
         window: Window,
@@ -1059,6 +1506,9 @@ impl XLib {
         if self.check_for_protocol(window, proto) {
             let mut data = xlib::ClientMessageData::default();
             data.set_long(0, proto as i64);
+            // `_NET_WM_PING` expects the window in the third field, other
+            // protocols ignore it
+            data.set_long(2, window as i64);
 
             let mut event = XEvent {
                 client_message: xlib::XClientMessageEvent {
@@ -1194,7 +1644,6 @@ impl XLib {
         }
     }
 
-    #[allow(dead_code)]
     fn ungrab_key_or_button(&self, binding: &KeyOrMouseBind, window: Window) {
         let modmask = binding.modifiers.as_modmask(self);
 
@@ -1300,8 +1749,8 @@ impl ModifierStateExt for ModifierState {
 impl WindowServerBackend for XLib {
     type Window = Window;
 
-    fn build() -> Self {
-        let xlib = Self::new();
+    fn build_with(config: BackendConfig) -> Self {
+        let xlib = Self::new(config);
         unsafe { xlib.init_as_wm() };
         xlib
     }
@@ -1318,6 +1767,55 @@ impl WindowServerBackend for XLib {
         }
     }
 
+    fn next_event_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Option<super::window_event::WindowEvent<Self::Window>> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            // xlib may have read events into its queue already, so check
+            // that before waiting on the connection
+            while unsafe { xlib::XPending(self.dpy()) } > 0 {
+                let ev = self.next_xevent();
+
+                if let Some(ev) = self.xevent_to_window_event(ev) {
+                    self.handle_event(ev.clone());
+                    return Some(ev);
+                }
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return None;
+            }
+
+            let mut fd = libc::pollfd {
+                fd: unsafe { xlib::XConnectionNumber(self.dpy()) },
+                events: libc::POLLIN,
+                revents: 0,
+            };
+
+            // round up so we don't wake up just before the deadline
+            let timeout_ms =
+                remaining.as_micros().div_ceil(1000).min(i32::MAX as u128);
+
+            // interrupted polls return so that signals can be handled
+            match unsafe { libc::poll(&mut fd, 1, timeout_ms as i32) } {
+                0 => return None,
+                -1 => {
+                    let err = std::io::Error::last_os_error();
+                    if err.kind() != std::io::ErrorKind::Interrupted {
+                        error!("failed to wait for X events: {}", err);
+                    }
+
+                    return None;
+                }
+                _ => {}
+            }
+        }
+    }
+
     fn handle_event(
         &mut self,
         event: super::window_event::WindowEvent<Self::Window>,
@@ -1367,6 +1865,35 @@ impl WindowServerBackend for XLib {
                         );
                     });
             }
+            // windows that aren't managed are configured as they requested
+            WindowEvent::ConfigureEvent(event) => {
+                let mut wc = xlib::XWindowChanges {
+                    x: event.position.x,
+                    y: event.position.y,
+                    width: event.size.width,
+                    height: event.size.height,
+                    border_width: event.border.unwrap_or(0),
+                    sibling: 0,
+                    stack_mode: 0,
+                };
+
+                let mut mask = xlib::CWX | xlib::CWY;
+                if event.size.width > 0 && event.size.height > 0 {
+                    mask |= xlib::CWWidth | xlib::CWHeight;
+                }
+                if event.border.is_some() {
+                    mask |= xlib::CWBorderWidth;
+                }
+
+                unsafe {
+                    xlib::XConfigureWindow(
+                        self.dpy(),
+                        event.window,
+                        mask as u32,
+                        &mut wc,
+                    );
+                }
+            }
             _ => {}
         }
     }
@@ -1380,6 +1907,7 @@ impl WindowServerBackend for XLib {
         &mut self,
         keybind: &super::window_event::KeyOrMouseBind,
     ) {
+        self.ungrab_key_or_button(keybind, self.connection.root());
         self.keybinds.retain(|kb| kb != keybind);
     }
 
@@ -1391,33 +1919,80 @@ impl WindowServerBackend for XLib {
                 xlib::RevertToPointerRoot,
                 xlib::CurrentTime,
             );
+        }
 
-            let border_color = self
-                .active_border_color
-                .as_ref()
-                .map(|color| color.pixel())
-                .unwrap_or_else(|| {
-                    xlib::XDefaultScreenOfDisplay(self.dpy())
+        self.connection.change_root_property_long(
+            self.atoms[ICCCMAtom::WmActiveWindow],
+            XA_WINDOW,
+            PropMode::Replace,
+            [window as c_long],
+        );
+
+        self.connection.change_root_property_long(
+            self.ewmh_atoms[EWMHAtom::NetActiveWindow],
+            XA_WINDOW,
+            PropMode::Replace,
+            [window as c_long],
+        );
+
+        self.active_window.set(Some(window));
+        self.paint_window_border(window, true);
+
+        self.send_protocol(window, self.atoms[ICCCMAtom::WmTakeFocus]);
+    }
+
+    fn paint_window_border(&self, window: Self::Window, focused: bool) {
+        unsafe {
+            let color = if focused {
+                self.active_border_color.as_ref()
+            } else {
+                self.inactive_border_color.as_ref()
+            };
+
+            let border_color =
+                color.map(|color| color.pixel()).unwrap_or_else(|| {
+                    let screen = xlib::XDefaultScreenOfDisplay(self.dpy())
                         .as_ref()
-                        .unwrap()
-                        .white_pixel
-                });
+                        .unwrap();
 
-            xlib::XSetWindowBorder(self.dpy(), window, border_color);
+                    if focused {
+                        screen.white_pixel
+                    } else {
+                        screen.black_pixel
+                    }
+                });
 
-            xlib::XChangeProperty(
-                self.dpy(),
-                self.connection.root(),
-                self.atoms[ICCCMAtom::WmActiveWindow],
-                xlib::XA_WINDOW,
-                32,
-                xlib::PropModeReplace,
-                &window as *const u64 as *const _,
-                1,
-            );
+            if !self.paint_double_border(window, border_color) {
+                xlib::XSetWindowBorder(self.dpy(), window, border_color);
+            }
         }
+    }
 
-        self.send_protocol(window, self.atoms[ICCCMAtom::WmTakeFocus]);
+    fn window_demands_attention(&self, window: Self::Window) -> bool {
+        let urgent = unsafe {
+            let hints = xlib::XGetWMHints(self.dpy(), window);
+
+            if hints.is_null() {
+                false
+            } else {
+                let urgent = (*hints).flags & xlib::XUrgencyHint != 0;
+                xlib::XFree(hints as *mut _);
+
+                urgent
+            }
+        };
+
+        urgent
+            || self
+                .has_net_wm_state(window, EWMHAtom::NetWmStateDemandsAttention)
+    }
+
+    fn set_window_urgent(&self, window: Self::Window, urgent: bool) {
+        self.set_net_wm_state(
+            window,
+            EWMHAtom::NetWmStateDemandsAttention,
+            urgent,
+        );
     }
 
     fn unfocus_window(&self, window: Self::Window) {
@@ -1428,28 +2003,23 @@ impl WindowServerBackend for XLib {
                 xlib::RevertToPointerRoot,
                 xlib::CurrentTime,
             );
+        }
 
-            // TODO: make painting the window border a seperate function, and configurable
-
-            let border_color = self
-                .inactive_border_color
-                .as_ref()
-                .map(|color| color.pixel())
-                .unwrap_or_else(|| {
-                    xlib::XDefaultScreenOfDisplay(self.dpy())
-                        .as_ref()
-                        .unwrap()
-                        .black_pixel
-                });
+        self.connection.delete_property(
+            self.connection.root(),
+            self.atoms[ICCCMAtom::WmActiveWindow],
+        );
 
-            xlib::XSetWindowBorder(self.dpy(), window, border_color);
+        self.connection.delete_property(
+            self.connection.root(),
+            self.ewmh_atoms[EWMHAtom::NetActiveWindow],
+        );
 
-            xlib::XDeleteProperty(
-                self.dpy(),
-                self.connection.root(),
-                self.atoms[ICCCMAtom::WmActiveWindow],
-            );
+        if self.active_window.get() == Some(window) {
+            self.active_window.set(None);
         }
+
+        self.paint_window_border(window, false);
     }
 
     fn raise_window(&self, window: Self::Window) {
@@ -1458,11 +2028,45 @@ impl WindowServerBackend for XLib {
         }
     }
 
+    fn lower_window(&self, window: Self::Window) {
+        unsafe {
+            xlib::XLowerWindow(self.dpy(), window);
+        }
+    }
+
     fn hide_window(&self, window: Self::Window) {
         let screen_size = self.screen_size() + Size::new(100, 100);
         self.move_window(window, screen_size.into());
     }
 
+    fn minimize_window(&self, window: Self::Window) {
+        unsafe {
+            xlib::XUnmapWindow(self.dpy(), window);
+        }
+
+        self.set_wm_state(window, WmState::Iconic);
+        self.set_net_wm_state(window, EWMHAtom::NetWmStateHidden, true);
+    }
+
+    fn restore_window(&self, window: Self::Window) {
+        unsafe {
+            xlib::XMapWindow(self.dpy(), window);
+        }
+
+        self.set_wm_state(window, WmState::Normal);
+        self.set_net_wm_state(window, EWMHAtom::NetWmStateHidden, false);
+    }
+
+    fn set_window_maximized(
+        &self,
+        window: Self::Window,
+        vert: bool,
+        horz: bool,
+    ) {
+        self.set_net_wm_state(window, EWMHAtom::NetWmStateMaximizedVert, vert);
+        self.set_net_wm_state(window, EWMHAtom::NetWmStateMaximizedHorz, horz);
+    }
+
     fn kill_window(&self, window: Self::Window) {
         if !self.send_protocol(window, self.atoms[ICCCMAtom::WmDeleteWindow]) {
             unsafe {
@@ -1471,6 +2075,20 @@ impl WindowServerBackend for XLib {
         }
     }
 
+    fn can_close_window(&self, window: Self::Window) -> bool {
+        self.check_for_protocol(window, self.atoms[ICCCMAtom::WmDeleteWindow])
+    }
+
+    fn ping_window(&self, window: Self::Window) -> bool {
+        self.send_protocol(window, self.ewmh_atoms[EWMHAtom::NetWmPing])
+    }
+
+    fn force_kill_window(&self, window: Self::Window) {
+        unsafe {
+            xlib::XKillClient(self.dpy(), window);
+        }
+    }
+
     fn get_parent_window(&self, window: Self::Window) -> Option<Self::Window> {
         let mut parent_window: Self::Window = 0;
         if unsafe {
@@ -1490,6 +2108,16 @@ impl WindowServerBackend for XLib {
         new_pos: Option<crate::util::Point<i32>>,
         new_border: Option<i32>,
     ) {
+        // a negative dimension would wrap to a huge unsigned size in the
+        // request sent to the X server
+        let new_size = new_size.filter(|size| {
+            let valid = size.to_u32().is_some();
+            if !valid {
+                warn!("not resizing {} to invalid size {:?}", window, size);
+            }
+            valid
+        });
+
         let position = new_pos.unwrap_or(Point::zero());
         let size = new_size.unwrap_or(Size::zero());
         let mut wc = xlib::XWindowChanges {
@@ -1520,7 +2148,140 @@ impl WindowServerBackend for XLib {
         unsafe {
             xlib::XConfigureWindow(self.dpy(), window, mask, &mut wc);
         }
-    }
+
+        // the border pixmap is drawn for the size of the window
+        if self.inner_border.is_some()
+            && (new_size.is_some() || new_border.is_some())
+        {
+            self.paint_window_border(
+                window,
+                self.active_window.get() == Some(window),
+            );
+        }
+    }
+
+    fn set_frame_extents(&self, window: Self::Window, border: i32) {
+        // left, right, top, bottom
+        self.connection.change_property_long(
+            window,
+            self.ewmh_atoms[EWMHAtom::NetFrameExtents],
+            XA_CARDINAL,
+            PropMode::Replace,
+            [border as c_long; 4],
+        );
+    }
+
+    fn notify_geometry(
+        &self,
+        window: Self::Window,
+        (position, size): (Point<i32>, Size<i32>),
+        border: i32,
+    ) {
+        let mut event: XEvent = xlib::XConfigureEvent {
+            type_: xlib::ConfigureNotify,
+            serial: 0,
+            send_event: 1,
+            display: self.dpy(),
+            event: window,
+            window,
+            x: position.x,
+            y: position.y,
+            width: size.width,
+            height: size.height,
+            border_width: border,
+            above: 0,
+            override_redirect: 0,
+        }
+        .into();
+
+        unsafe {
+            xlib::XSendEvent(
+                self.dpy(),
+                window,
+                0,
+                xlib::StructureNotifyMask,
+                &mut event,
+            );
+        }
+    }
+
+    fn get_window_desktop(&self, window: Self::Window) -> Option<usize> {
+        self.connection
+            .get_property_long(
+                window,
+                self.ewmh_atoms[EWMHAtom::NetWmDesktop],
+                XA_CARDINAL,
+            )
+            .and_then(|desktop| desktop.first().map(|&d| d as u32))
+            // 0xFFFFFFFF requests the window on all desktops
+            .filter(|&desktop| desktop != u32::MAX)
+            .map(|desktop| desktop as usize)
+    }
+
+    fn get_window_icon(&self, window: Self::Window) -> Option<Vec<Icon>> {
+        self.connection
+            .get_property_long(
+                window,
+                self.ewmh_atoms[EWMHAtom::NetWmIcon],
+                XA_CARDINAL,
+            )
+            .map(|data| decode_icons(&data))
+            .filter(|icons| !icons.is_empty())
+    }
+
+    fn get_window_pid(&self, window: Self::Window) -> Option<u32> {
+        self.connection
+            .get_property_long(
+                window,
+                self.ewmh_atoms[EWMHAtom::NetWmPid],
+                XA_CARDINAL,
+            )
+            .and_then(|pid| pid.first().map(|&pid| pid as u32))
+    }
+
+    fn get_window_aspect(&self, window: Self::Window) -> Option<(f32, f32)> {
+        let hints = unsafe {
+            let mut hints = std::mem::MaybeUninit::<xlib::XSizeHints>::zeroed()
+                .assume_init();
+            let mut supplied = 0;
+
+            if xlib::XGetWMNormalHints(
+                self.dpy(),
+                window,
+                &mut hints,
+                &mut supplied,
+            ) == 0
+            {
+                return None;
+            }
+
+            hints
+        };
+
+        let ratio = |aspect: xlib::AspectRatio| {
+            (aspect.x > 0 && aspect.y > 0)
+                .then(|| aspect.x as f32 / aspect.y as f32)
+        };
+
+        if hints.flags & xlib::PAspect == 0 {
+            return None;
+        }
+
+        Some((ratio(hints.min_aspect)?, ratio(hints.max_aspect)?))
+            .filter(|(min, max)| min <= max)
+    }
+
+    fn display_name(&self) -> Option<String> {
+        unsafe {
+            let name = xlib::XDisplayString(self.dpy());
+
+            (!name.is_null()).then(|| {
+                std::ffi::CStr::from_ptr(name)
+                    .to_string_lossy()
+                    .into_owned()
+            })
+        }
+    }
 
     fn screen_size(&self) -> Size<i32> {
         unsafe {
@@ -1544,7 +2305,147 @@ impl WindowServerBackend for XLib {
             .map(|wa| (wa.width, wa.height).into())
     }
 
-    fn grab_cursor(&self) {
+    fn get_window_rect(
+        &self,
+        window: Self::Window,
+    ) -> Option<(Point<i32>, Size<i32>)> {
+        let wa = self.get_window_attributes(window)?;
+
+        let (mut x, mut y) = (0, 0);
+        let mut child = 0;
+
+        // the attributes are relative to the parent window
+        let translated = unsafe {
+            xlib::XTranslateCoordinates(
+                self.dpy(),
+                window,
+                self.connection.root(),
+                0,
+                0,
+                &mut x,
+                &mut y,
+                &mut child,
+            ) != 0
+        };
+
+        translated.then(|| {
+            (
+                // the origin of `window` is inside its border
+                Point::new(x - wa.border_width, y - wa.border_width),
+                Size::new(wa.width, wa.height),
+            )
+        })
+    }
+
+    fn set_window_opacity(&self, window: Self::Window, opacity: f32) {
+        let atom = self.ewmh_atoms[EWMHAtom::NetWmWindowOpacity];
+
+        if opacity >= 1.0 {
+            self.connection.delete_property(window, atom);
+        } else {
+            let opacity = (opacity.max(0.0) * u32::MAX as f32) as u32;
+
+            self.connection.change_property_long(
+                window,
+                atom,
+                XA_CARDINAL,
+                PropMode::Replace,
+                [opacity as c_long],
+            );
+        }
+    }
+
+    fn show_switcher(&mut self, entries: &[String], selected: usize) -> bool {
+        if self.switcher.is_none() {
+            self.switcher = Switcher::new(
+                self.connection.display(),
+                self.connection.screen(),
+                self.connection.root(),
+                self.screen_size(),
+            );
+
+            if self.switcher.is_none() {
+                warn!("failed to create window switcher");
+                return false;
+            }
+
+            if !self.grab_keyboard() {
+                warn!("failed to grab the keyboard for the window switcher");
+                self.switcher = None;
+                return false;
+            }
+        }
+
+        if let Some(switcher) = self.switcher.as_mut() {
+            switcher.update(entries, selected);
+        }
+
+        true
+    }
+
+    fn hide_switcher(&mut self) {
+        if self.switcher.take().is_some() {
+            unsafe {
+                xlib::XUngrabKeyboard(self.dpy(), xlib::CurrentTime);
+            }
+        }
+    }
+
+    fn grab_server(&self) {
+        if self.server_grabs.get() == 0 {
+            unsafe {
+                xlib::XGrabServer(self.dpy());
+            }
+        }
+
+        self.server_grabs.set(self.server_grabs.get() + 1);
+    }
+
+    fn flush(&self) {
+        unsafe {
+            xlib::XSync(self.dpy(), 0);
+        }
+    }
+
+    fn cleanup(&self) {
+        let root = self.connection.root();
+
+        for atom in [
+            EWMHAtom::NetSupportingWmCheck,
+            EWMHAtom::NetClientList,
+            EWMHAtom::NetActiveWindow,
+        ] {
+            self.connection.delete_property(root, self.ewmh_atoms[atom]);
+        }
+
+        unsafe {
+            xlib::XUngrabKey(self.dpy(), xlib::AnyKey, xlib::AnyModifier, root);
+            xlib::XUngrabButton(
+                self.dpy(),
+                xlib::AnyButton as u32,
+                xlib::AnyModifier,
+                root,
+            );
+            xlib::XDestroyWindow(self.dpy(), self.wm_window);
+            xlib::XSync(self.dpy(), 0);
+        }
+    }
+
+    fn ungrab_server(&self) {
+        match self.server_grabs.get() {
+            0 => warn!("ungrab_server called without grab_server"),
+            1 => {
+                self.server_grabs.set(0);
+
+                unsafe {
+                    xlib::XUngrabServer(self.dpy());
+                }
+            }
+            n => self.server_grabs.set(n - 1),
+        }
+    }
+
+    fn grab_cursor(&self, shape: CursorShape) {
         unsafe {
             xlib::XGrabPointer(
                 self.dpy(),
@@ -1556,7 +2457,7 @@ impl WindowServerBackend for XLib {
                 xlib::GrabModeAsync,
                 xlib::GrabModeAsync,
                 0,
-                0,
+                self.cursors[shape as usize],
                 xlib::CurrentTime,
             );
         }
@@ -1604,7 +2505,10 @@ impl WindowServerBackend for XLib {
             let windows = unsafe {
                 std::slice::from_raw_parts(children, num_children as usize)
                     .to_vec()
-            };
+            }
+            .into_iter()
+            .filter(|&window| self.is_manageable(window))
+            .collect::<Vec<_>>();
 
             unsafe { xlib::XFree(children as *mut _) };
 
@@ -1613,21 +2517,84 @@ impl WindowServerBackend for XLib {
     }
 
     fn set_active_window_border_color(&mut self, color_name: &str) {
-        self.active_border_color = color::XftColor::new(
-            self.connection.display(),
-            self.connection.screen(),
-            color_name.to_owned(),
-        )
-        .ok();
+        self.active_border_color = self.alloc_color(color_name);
     }
 
     fn set_inactive_window_border_color(&mut self, color_name: &str) {
-        self.inactive_border_color = color::XftColor::new(
-            self.connection.display(),
-            self.connection.screen(),
-            color_name.to_owned(),
-        )
-        .ok();
+        self.inactive_border_color = self.alloc_color(color_name);
+    }
+
+    fn set_inner_border(&mut self, color_name: &str, width: i32) {
+        self.inner_border = if width > 0 {
+            self.alloc_color(color_name).map(|color| (color, width))
+        } else {
+            None
+        };
+    }
+
+    fn set_root_color(&self, color_name: &str) {
+        if let Some(color) = self.alloc_color(color_name) {
+            unsafe {
+                xlib::XSetWindowBackground(
+                    self.dpy(),
+                    self.connection.root(),
+                    color.pixel(),
+                );
+                xlib::XClearWindow(self.dpy(), self.connection.root());
+            }
+        }
+    }
+
+    fn bell(&self) {
+        unsafe {
+            xlib::XBell(self.dpy(), 0);
+        }
+    }
+
+    fn set_layout_name(&self, name: &str) {
+        if let Some(atom) = self.layout_atom {
+            self.connection.change_root_property_byte(
+                atom,
+                self.atoms[ICCCMAtom::Utf8String],
+                PropMode::Replace,
+                name,
+            );
+        }
+    }
+
+    fn set_work_areas(&self, areas: &[Rect], viewports: &[Point<i32>]) {
+        let areas = areas
+            .iter()
+            .flat_map(|area| {
+                [
+                    area.origin.x,
+                    area.origin.y,
+                    area.size.width,
+                    area.size.height,
+                ]
+            })
+            .map(|value| value as c_long)
+            .collect::<Vec<_>>();
+
+        self.connection.change_root_property_long(
+            self.ewmh_atoms[EWMHAtom::NetWorkarea],
+            XA_CARDINAL,
+            PropMode::Replace,
+            areas,
+        );
+
+        let viewports = viewports
+            .iter()
+            .flat_map(|viewport| [viewport.x, viewport.y])
+            .map(|value| value as c_long)
+            .collect::<Vec<_>>();
+
+        self.connection.change_root_property_long(
+            self.ewmh_atoms[EWMHAtom::NetDesktopViewport],
+            XA_CARDINAL,
+            PropMode::Replace,
+            viewports,
+        );
     }
 
     fn get_window_name(&self, window: Self::Window) -> Option<String> {
@@ -1639,6 +2606,26 @@ impl WindowServerBackend for XLib {
             })
     }
 
+    fn get_window_role(&self, window: Self::Window) -> Option<String> {
+        self.connection
+            .get_text_property(window, self.atoms[ICCCMAtom::WmWindowRole])
+    }
+
+    fn get_window_strut(&self, window: Self::Window) -> Option<Strut> {
+        // the first 4 values of `_NET_WM_STRUT_PARTIAL` are the same as
+        // `_NET_WM_STRUT`
+        self.get_strut_property(
+            window,
+            self.ewmh_atoms[EWMHAtom::NetWmStrutPartial],
+        )
+        .or_else(|| {
+            self.get_strut_property(
+                window,
+                self.ewmh_atoms[EWMHAtom::NetWmStrut],
+            )
+        })
+    }
+
     fn get_window_type(
         &self,
         window: Self::Window,
@@ -1660,6 +2647,294 @@ impl WindowServerBackend for XLib {
     }
 }
 
+impl XLib {
+    /// returns `true` if an existing `window` should be managed: it is
+    /// mapped, doesn't bypass the window manager with `override_redirect`
+    /// and isn't one of the window manager's own windows.
+    fn is_manageable(&self, window: Window) -> bool {
+        if window == self.wm_window
+            || self
+                .switcher
+                .as_ref()
+                .is_some_and(|switcher| switcher.window() == window)
+        {
+            return false;
+        }
+
+        self.get_window_attributes(window).is_some_and(|wa| {
+            wa.map_state == xlib::IsViewable && wa.override_redirect == 0
+        })
+    }
+
+    /**
+    Draws the border of `window` as a pixmap in `outer_color` with the inner border along the
+    inside of it. Returns `false` if no inner border is configured or the window's border is
+    too thin to fit it, in which case the border has to be set to a solid color instead.
+    */
+    fn paint_double_border(&self, window: Window, outer_color: u64) -> bool {
+        let (inner_color, inner) = match self.inner_border.as_ref() {
+            Some((color, width)) => (color.pixel(), *width),
+            None => return false,
+        };
+
+        let wa = match self.get_window_attributes(window) {
+            Some(wa) if wa.border_width > inner => wa,
+            _ => return false,
+        };
+
+        let (w, h, b) = (wa.width, wa.height, wa.border_width);
+        let size = match Size::new(w + 2 * b, h + 2 * b).to_u32() {
+            Some(size) => size,
+            None => return false,
+        };
+
+        // the border pixmap is tiled from the origin of the window's
+        // contents, so the left and top borders wrap around to the far end
+        // of the pixmap.
+        let rect = |x: i32, y: i32, width: i32, height: i32| xlib::XRectangle {
+            x: x as i16,
+            y: y as i16,
+            width: width as u16,
+            height: height as u16,
+        };
+        let mut rects = [
+            // right and left
+            rect(w, 0, inner, h + inner),
+            rect(w + 2 * b - inner, 0, inner, h + inner),
+            // bottom and top
+            rect(0, h, w + inner, inner),
+            rect(0, h + 2 * b - inner, w + inner, inner),
+            // top left corner
+            rect(w + 2 * b - inner, h + 2 * b - inner, inner, inner),
+        ];
+
+        unsafe {
+            let pixmap = xlib::XCreatePixmap(
+                self.dpy(),
+                window,
+                size.width,
+                size.height,
+                wa.depth as u32,
+            );
+            let gc =
+                xlib::XCreateGC(self.dpy(), pixmap, 0, std::ptr::null_mut());
+
+            xlib::XSetForeground(self.dpy(), gc, outer_color);
+            xlib::XFillRectangle(
+                self.dpy(),
+                pixmap,
+                gc,
+                0,
+                0,
+                size.width,
+                size.height,
+            );
+
+            xlib::XSetForeground(self.dpy(), gc, inner_color);
+            xlib::XFillRectangles(
+                self.dpy(),
+                pixmap,
+                gc,
+                rects.as_mut_ptr(),
+                rects.len() as i32,
+            );
+
+            xlib::XSetWindowBorderPixmap(self.dpy(), window, pixmap);
+
+            xlib::XFreeGC(self.dpy(), gc);
+            xlib::XFreePixmap(self.dpy(), pixmap);
+        }
+
+        true
+    }
+
+    /// returns `true` if `state` is listed in the `_NET_WM_STATE` of `window`.
+    fn has_net_wm_state(&self, window: Window, state: EWMHAtom) -> bool {
+        self.connection
+            .get_property_long(
+                window,
+                self.ewmh_atoms[EWMHAtom::NetWmState],
+                xlib::XA_ATOM,
+            )
+            .is_some_and(|states| {
+                states.contains(&(self.ewmh_atoms[state] as c_long))
+            })
+    }
+
+    /// translates a `_NET_WM_STATE` request to remove (0), add (1) or toggle
+    /// (2) `state` into an event, if the state is supported. `states` are all
+    /// states of the request, both maximized states are handled together.
+    fn net_wm_state_event(
+        &self,
+        window: Window,
+        state: EWMHAtom,
+        states: &[EWMHAtom],
+        action: c_long,
+    ) -> Option<XLibWindowEvent> {
+        match state {
+            EWMHAtom::NetWmStateFullscreen => {
+                let state = match action {
+                    0 => FullscreenState::Off,
+                    1 => FullscreenState::On,
+                    2 => FullscreenState::Toggle,
+                    _ => return None,
+                };
+
+                debug!("fullscreen event");
+                Some(XLibWindowEvent::FullscreenEvent(FullscreenEvent::new(
+                    window, state,
+                )))
+            }
+            EWMHAtom::NetWmStateMaximizedVert
+            | EWMHAtom::NetWmStateMaximizedHorz => {
+                let maximize = match action {
+                    0 => MaximizeState::Off,
+                    1 => MaximizeState::On,
+                    2 => MaximizeState::Toggle,
+                    _ => return None,
+                };
+
+                debug!("maximize event");
+                Some(XLibWindowEvent::MaximizeEvent(MaximizeEvent::new(
+                    window,
+                    states.contains(&EWMHAtom::NetWmStateMaximizedVert),
+                    states.contains(&EWMHAtom::NetWmStateMaximizedHorz),
+                    maximize,
+                )))
+            }
+            EWMHAtom::NetWmStateDemandsAttention => {
+                let urgent = match action {
+                    0 => false,
+                    1 => true,
+                    2 => !self.has_net_wm_state(window, state),
+                    _ => return None,
+                };
+
+                Some(XLibWindowEvent::UrgencyEvent(UrgencyEvent::new(
+                    window, urgent,
+                )))
+            }
+            _ => None,
+        }
+    }
+
+    /// adds or removes `state` from the `_NET_WM_STATE` of `window`.
+    fn set_net_wm_state(&self, window: Window, state: EWMHAtom, set: bool) {
+        let atom = self.ewmh_atoms[state] as c_long;
+
+        let mut states = self
+            .connection
+            .get_property_long(
+                window,
+                self.ewmh_atoms[EWMHAtom::NetWmState],
+                xlib::XA_ATOM,
+            )
+            .unwrap_or_default();
+
+        states.retain(|&a| a != atom);
+        if set {
+            states.push(atom);
+        }
+
+        self.connection.change_property_long(
+            window,
+            self.ewmh_atoms[EWMHAtom::NetWmState],
+            xlib::XA_ATOM,
+            PropMode::Replace,
+            states,
+        );
+    }
+
+    /// allocates `color_name`, logging a warning if it isn't a valid color.
+    /// window borders fall back to the screen's white/black pixel if this
+    /// returns `None`.
+    /// grabs the keyboard, retrying for up to a second like dwm while
+    /// another client, e.g. a menu that is just closing, still holds it.
+    fn grab_keyboard(&self) -> bool {
+        for _ in 0..1000 {
+            let result = unsafe {
+                xlib::XGrabKeyboard(
+                    self.dpy(),
+                    self.connection.root(),
+                    1,
+                    xlib::GrabModeAsync,
+                    xlib::GrabModeAsync,
+                    xlib::CurrentTime,
+                )
+            };
+
+            if result == xlib::GrabSuccess {
+                return true;
+            }
+
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        false
+    }
+
+    fn alloc_color(&self, color_name: &str) -> Option<color::XftColor> {
+        color::XftColor::new(
+            self.connection.display(),
+            self.connection.screen(),
+            color_name.to_owned(),
+        )
+        .map_err(|_| {
+            warn!(
+                "invalid color {:?}, falling back to the default color.",
+                color_name
+            )
+        })
+        .ok()
+    }
+
+    /// sets the ICCCM `WM_STATE` of `window` to `state`.
+    fn set_wm_state(&self, window: Window, state: WmState) {
+        self.connection.change_property_long(
+            window,
+            self.atoms[ICCCMAtom::WmState],
+            self.atoms[ICCCMAtom::WmState],
+            PropMode::Replace,
+            [state as c_long, 0],
+        );
+    }
+
+    fn get_strut_property(&self, window: Window, atom: Atom) -> Option<Strut> {
+        self.connection
+            .get_property_long(window, atom, XA_CARDINAL)
+            .filter(|strut| strut.len() >= 4)
+            .map(|strut| {
+                Strut::new(
+                    strut[0] as i32,
+                    strut[1] as i32,
+                    strut[2] as i32,
+                    strut[3] as i32,
+                )
+            })
+    }
+}
+
+/// decodes the packed `_NET_WM_ICON` format: each icon is its width and
+/// height followed by `width * height` ARGB pixels. decoding stops at the
+/// first truncated or empty icon.
+fn decode_icons(data: &[c_long]) -> Vec<Icon> {
+    let mut icons = Vec::new();
+    let mut data = data.iter().map(|&value| value as u32);
+
+    while let (Some(width), Some(height)) = (data.next(), data.next()) {
+        let len = width as usize * height as usize;
+        let pixels = data.by_ref().take(len).collect::<Vec<_>>();
+
+        if len == 0 || pixels.len() != len {
+            break;
+        }
+
+        icons.push((width, height, pixels));
+    }
+
+    icons
+}
+
 impl TryFrom<EWMHAtom> for WindowType {
     type Error = ();
 
@@ -1678,7 +2953,6 @@ impl TryFrom<EWMHAtom> for WindowType {
     }
 }
 
-#[allow(dead_code)]
 unsafe extern "C" fn xlib_error_handler(
     _dpy: *mut x11::xlib::Display,
     ee: *mut x11::xlib::XErrorEvent,
@@ -1783,3 +3057,62 @@ pub mod xpointer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backends::structs::largest_icon;
+
+    #[test]
+    fn decode_net_wm_icon() {
+        let data: Vec<c_long> = vec![
+            1, 1, 0xff00ff00, // 1x1
+            2, 2, 1, 2, 3, 4, // 2x2
+            3, 3, 5, // truncated
+        ];
+
+        let icons = decode_icons(&data);
+
+        assert_eq!(
+            icons,
+            vec![(1, 1, vec![0xff00ff00]), (2, 2, vec![1, 2, 3, 4])]
+        );
+        assert_eq!(largest_icon(&icons), Some(&(2, 2, vec![1, 2, 3, 4])));
+    }
+
+    #[test]
+    fn large_icons_are_read_in_chunks() {
+        let icon = |size: u32| {
+            let pixels = (0..size * size).map(|p| p as c_long);
+            vec![size as c_long, size as c_long]
+                .into_iter()
+                .chain(pixels)
+        };
+        let property = icon(16).chain(icon(32)).collect::<Vec<_>>();
+
+        // serves the property like the server, at most a chunk at a time
+        let data = connection::read_property_chunks(|offset| {
+            let rest = &property[offset as usize..];
+            let len = rest.len().min(1024);
+
+            Some(connection::PropertyChunk {
+                data: rest[..len]
+                    .iter()
+                    .flat_map(|value| value.to_ne_bytes())
+                    .collect(),
+                wire_len: len * 4,
+                bytes_after: (rest.len() - len) * 4,
+            })
+        })
+        .unwrap();
+
+        let longs = data
+            .chunks(size_of::<c_long>())
+            .map(bytemuck::pod_read_unaligned::<c_long>)
+            .collect::<Vec<_>>();
+        let icons = decode_icons(&longs);
+
+        assert_eq!(icons.len(), 2);
+        assert_eq!(largest_icon(&icons).map(|i| (i.0, i.1)), Some((32, 32)));
+    }
+}
@@ -1,19 +1,20 @@
-use std::{ops::Rem, usize};
+use std::{collections::BTreeSet, ops::Rem, usize};
 
 use indexmap::IndexMap;
 use log::error;
 use num_traits::Zero;
 
-use crate::backends::structs::WindowType;
+use crate::backends::structs::{Strut, WindowType};
+use crate::state::Direction;
 use crate::util::BuildIdentityHasher;
-use crate::util::{Point, Size};
+use crate::util::{Point, Rect, Size};
 
 mod client {
     use std::hash::{Hash, Hasher};
 
     use crate::{
-        backends::structs::WindowType,
-        util::{Point, Size},
+        backends::structs::{Strut, WindowType},
+        util::{Point, Rect, Size},
     };
     use x11::xlib::Window;
 
@@ -22,9 +23,38 @@ mod client {
         pub(crate) window: Window,
         pub(crate) size: Size<i32>,
         pub(crate) position: Point<i32>,
+        /// border width last applied to the window, see
+        /// `ClientState::update_border`.
+        pub(crate) border: i32,
+        /// geometry last applied to the window, `None` if it has to be
+        /// reconfigured, see `ClientState::update_geometry`.
+        pub(crate) applied: Option<(Point<i32>, Size<i32>)>,
         pub(crate) parent_window: Option<Window>,
         pub(crate) window_type: WindowType,
         pub(crate) fullscreen: bool,
+        /// space this client reserves at the screen edges, e.g. for a bar.
+        pub(crate) strut: Option<Strut>,
+        /// whether the client is maximized (vertically, horizontally)
+        pub(crate) maximized: (bool, bool),
+        /// floating geometry to restore once the client is no longer maximized.
+        pub(crate) unmaximized: Option<(Point<i32>, Size<i32>)>,
+        /// floating geometry to restore once the client is floated again or
+        /// leaves fullscreen.
+        pub(crate) saved_geometry: Option<(Point<i32>, Size<i32>)>,
+        /// process id of the client, if it advertises it.
+        pub(crate) pid: Option<u32>,
+        /// terminal hidden in place of this client until it is removed, see
+        /// `ClientState::swallow`.
+        pub(crate) swallowed: Option<Window>,
+        /// smallest and largest width to height ratio the client accepts.
+        pub(crate) min_aspect: Option<f32>,
+        pub(crate) max_aspect: Option<f32>,
+        /// pinned by the user to stay above all other clients while floating,
+        /// unlike `_NET_WM_STATE_ABOVE` which clients request themselves.
+        pub(crate) pinned: bool,
+        /// share of its stack the client gets when tiled, relative to the
+        /// other clients in the stack.
+        pub(crate) weight: f32,
     }
 
     impl Default for Client {
@@ -33,9 +63,21 @@ mod client {
                 window: 0,
                 size: (100, 100).into(),
                 position: (0, 0).into(),
+                border: 0,
+                applied: None,
                 parent_window: None,
                 fullscreen: false,
                 window_type: WindowType::Normal,
+                strut: None,
+                maximized: (false, false),
+                unmaximized: None,
+                saved_geometry: None,
+                pid: None,
+                swallowed: None,
+                min_aspect: None,
+                max_aspect: None,
+                pinned: false,
+                weight: 1.0,
             }
         }
     }
@@ -93,6 +135,30 @@ mod client {
             Self { size, ..self }
         }
 
+        pub fn with_position(self, position: Point<i32>) -> Self {
+            Self { position, ..self }
+        }
+
+        pub fn with_border(self, border: i32) -> Self {
+            Self { border, ..self }
+        }
+
+        pub fn with_strut(self, strut: Option<Strut>) -> Self {
+            Self { strut, ..self }
+        }
+
+        pub fn with_pid(self, pid: Option<u32>) -> Self {
+            Self { pid, ..self }
+        }
+
+        pub fn with_aspect(self, aspect: Option<(f32, f32)>) -> Self {
+            Self {
+                min_aspect: aspect.map(|(min, _)| min),
+                max_aspect: aspect.map(|(_, max)| max),
+                ..self
+            }
+        }
+
         /// toggles the clients fullscreen flag.
         /// returns `true` if the client is now fullscreen.
         pub fn toggle_fullscreen(&mut self) -> bool {
@@ -114,9 +180,70 @@ mod client {
             self.fullscreen
         }
 
+        /// shrinks whichever dimension of `size` is too large for the aspect
+        /// ratios the client accepts.
+        pub fn constrain_aspect(&self, size: Size<i32>) -> Size<i32> {
+            let ratio = size.width as f32 / size.height as f32;
+
+            match (self.min_aspect, self.max_aspect) {
+                (Some(min), _) if ratio < min => Size::new(
+                    size.width,
+                    ((size.width as f32 / min).round() as i32).max(1),
+                ),
+                (_, Some(max)) if ratio > max => Size::new(
+                    ((size.height as f32 * max).round() as i32).max(1),
+                    size.height,
+                ),
+                _ => size,
+            }
+        }
+
+        pub fn is_pinned(&self) -> bool {
+            self.pinned
+        }
+
+        pub fn is_desktop(&self) -> bool {
+            self.window_type == WindowType::Desktop
+        }
+
+        /// docks and desktops place themselves, they are never moved, focused
+        /// or given a border.
+        pub fn is_fixed(&self) -> bool {
+            matches!(self.window_type, WindowType::Dock | WindowType::Desktop)
+        }
+
         pub fn has_parent_window(&self) -> bool {
             self.parent_window.is_some()
         }
+
+        pub fn is_maximized(&self) -> bool {
+            self.maximized.0 || self.maximized.1
+        }
+
+        /// stretches the client over the area in the axes it is maximized in.
+        pub(crate) fn apply_maximized(&mut self, area: Rect, border: i32) {
+            let (vert, horz) = self.maximized;
+
+            if vert {
+                self.position.y = area.origin.y;
+                self.size.height = area.size.height - border * 2;
+            }
+
+            if horz {
+                self.position.x = area.origin.x;
+                self.size.width = area.size.width - border * 2;
+            }
+        }
+
+        /// the area covered by the client, without its border.
+        pub fn rect(&self) -> Rect {
+            Rect::new(self.position, self.size)
+        }
+
+        /// returns true if `point` lies within the client's rectangle.
+        pub fn contains_point(&self, point: Point<i32>) -> bool {
+            self.rect().contains(point)
+        }
     }
 
     impl Hash for Client {
@@ -181,6 +308,131 @@ pub enum ClientEntry<T> {
     Vacant,
 }
 
+/// Where new tiled clients are placed on the current virtual screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+pub enum AttachPolicy {
+    /// at the top of the master stack, pushing the last master client to aux
+    Master,
+    /// at the top of the aux stack
+    Aux,
+    /// at the bottom of the aux stack
+    #[default]
+    Bottom,
+}
+
+/// Where the master stack is placed by layouts that put it on the left,
+/// `MasterStack` and `Spiral`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+pub enum MasterPosition {
+    #[default]
+    Left,
+    /// mirrors the layout horizontally
+    Right,
+    /// transposes the layout, the master stack takes its share of the height
+    Top,
+    /// transposes and mirrors the layout vertically
+    Bottom,
+}
+
+impl MasterPosition {
+    /// maps a tile of `area` laid out with the master stack on the left to
+    /// this position.
+    fn orient(self, tile: Rect, area: Rect) -> Rect {
+        let Rect { origin, size } = area;
+
+        let mirror_x = |r: Rect| {
+            Rect::new(
+                Point::new(origin.x + area.right() - r.right(), r.origin.y),
+                r.size,
+            )
+        };
+        let mirror_y = |r: Rect| {
+            Rect::new(
+                Point::new(r.origin.x, origin.y + area.bottom() - r.bottom()),
+                r.size,
+            )
+        };
+
+        // scales edges between the axes so that shares of the width become
+        // the same shares of the height and vice versa
+        let scale = |v: i32, from: i32, to: i32, len_from: i32, len_to: i32| {
+            to + ((v - from) as i64 * len_to as i64 / len_from.max(1) as i64)
+                as i32
+        };
+        let transpose = |r: Rect| {
+            let x = |y| scale(y, origin.y, origin.x, size.height, size.width);
+            let y = |x| scale(x, origin.x, origin.y, size.width, size.height);
+
+            Rect::new(
+                Point::new(x(r.origin.y), y(r.origin.x)),
+                Size::new(
+                    x(r.bottom()) - x(r.origin.y),
+                    y(r.right()) - y(r.origin.x),
+                ),
+            )
+        };
+
+        match self {
+            MasterPosition::Left => tile,
+            MasterPosition::Right => mirror_x(tile),
+            MasterPosition::Top => transpose(tile),
+            MasterPosition::Bottom => mirror_y(transpose(tile)),
+        }
+    }
+}
+
+/// How the tiled clients of a virtual screen are arranged.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    serde::Deserialize,
+    strum::EnumIter,
+    strum::IntoStaticStr,
+)]
+pub enum LayoutMode {
+    /// master stack on the left, aux stack on the right
+    #[default]
+    MasterStack,
+    /// every client takes half of the remaining space, spiraling inwards
+    Spiral,
+    /// master stack on the top, aux stack on the bottom
+    BottomStack,
+}
+
+/// Placement of a single client, see `ClientState::serialize_layout`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct LayoutEntry {
+    pub window: u64,
+    /// index of the virtual screen of a tiled client
+    pub virtual_screen: Option<usize>,
+    pub master: bool,
+    pub floating: bool,
+    pub position: (i32, i32),
+    pub size: (i32, i32),
+}
+
+/// Client placement that can be saved and restored across restarts.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct SavedLayout {
+    pub current_virtual_screen: usize,
+    pub clients: Vec<LayoutEntry>,
+}
+
+/// Summary of a virtual screen for status bars, see
+/// `ClientState::workspace_occupancy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkspaceInfo {
+    pub index: usize,
+    /// number of tiled clients on the virtual screen
+    pub window_count: usize,
+    pub is_current: bool,
+    pub has_urgent: bool,
+}
+
 #[derive(Debug)]
 pub struct ClientState {
     pub(self) clients: Clients,
@@ -188,22 +440,70 @@ pub struct ClientState {
     focused: Option<ClientRef>,
     pub(self) virtual_screens: VirtualScreenStore,
 
-    pub(self) gap: i32,
+    /// gap between adjacent tiled clients
+    pub(self) inner_gap: i32,
+    /// gap between tiled clients and the edge of the work area
+    pub(self) outer_gap: i32,
+    /// size of the whole screen, fullscreen clients cover it including the
+    /// space reserved by struts, see `get_work_area`
     pub(self) screen_size: Size<i32>,
     pub(self) master_size: f32,
+    /// like `master_size`, but for layouts that split the screen vertically
+    pub(self) master_height: f32,
     border_size: i32,
+    /// border width of unfocused clients, `border_size` if unset
+    unfocused_border_size: Option<i32>,
+    /// whether space reserved by client struts is excluded from the tiling area
+    struts_enabled: bool,
+    /// screen margins excluded from the work area regardless of struts
+    reserved: Strut,
+    /// keep floating clients within the work area, see
+    /// `constrain_to_work_area`
+    constrain_floating: bool,
+    /// part of a constrained client that has to stay within the work area,
+    /// all of it if unset
+    constrain_margin: Option<i32>,
+    attach_policy: AttachPolicy,
+    master_position: MasterPosition,
+    /// window types that never receive the focus
+    unfocusable_types: Vec<WindowType>,
+    /// minimized clients, in the order they were minimized
+    minimized: ClientRefs,
+    /// urgent clients, in the order they became urgent
+    urgent: ClientRefs,
+    /// values restored by `reset_layout_params`
+    default_gaps: (i32, i32),
+    default_layout: LayoutMode,
 }
 
+const DEFAULT_MASTER_SIZE: f32 = 1.0;
+
+pub const DEFAULT_UNFOCUSABLE_TYPES: [WindowType; 3] =
+    [WindowType::Dock, WindowType::Desktop, WindowType::Splash];
+
 #[derive(Debug, Clone)]
 struct VirtualScreen {
     master: ClientRefs,
     aux: ClientRefs,
+    /// number of clients kept on the master stack
+    nmaster: usize,
+    layout: LayoutMode,
+    /// clients floated by `ClientState::toggle_all_floating`, in tiling order
+    floated: Option<ClientRefs>,
+    /// client last focused while this screen was current
+    focused: Option<ClientRef>,
+    /// tiled client covering the whole tiling area, hiding its peers
+    zoomed: Option<ClientRef>,
 }
 
 #[derive(Debug)]
 struct VirtualScreenStore {
     screens: Vec<VirtualScreen>,
+    /// the primary viewed screen, its layout is used when viewing multiple screens
+    /// and new clients are added to it.
     current_idx: usize,
+    /// all viewed screens, always contains `current_idx`.
+    current_indices: BTreeSet<usize>,
     last_idx: Option<usize>,
 }
 
@@ -214,10 +514,24 @@ impl Default for ClientState {
             floating_clients: Default::default(),
             focused: None,
             virtual_screens: VirtualScreenStore::new(1),
-            gap: 0,
+            inner_gap: 0,
+            outer_gap: 0,
             screen_size: (1, 1).into(),
-            master_size: 1.0,
+            master_size: DEFAULT_MASTER_SIZE,
+            master_height: DEFAULT_MASTER_SIZE,
             border_size: 0,
+            unfocused_border_size: None,
+            struts_enabled: true,
+            reserved: Strut::default(),
+            constrain_floating: false,
+            constrain_margin: None,
+            attach_policy: AttachPolicy::default(),
+            master_position: MasterPosition::default(),
+            unfocusable_types: DEFAULT_UNFOCUSABLE_TYPES.to_vec(),
+            minimized: Vec::new(),
+            urgent: Vec::new(),
+            default_gaps: (0, 0),
+            default_layout: LayoutMode::default(),
         }
     }
 }
@@ -227,8 +541,18 @@ impl ClientState {
         Self::default()
     }
 
+    /// sets both the inner and the outer gap to `gap`.
     pub fn with_gap(self, gap: i32) -> Self {
-        Self { gap, ..self }
+        self.with_gaps(gap, gap)
+    }
+
+    pub fn with_gaps(self, inner_gap: i32, outer_gap: i32) -> Self {
+        Self {
+            inner_gap,
+            outer_gap,
+            default_gaps: (inner_gap, outer_gap),
+            ..self
+        }
     }
 
     pub fn with_border(self, border: i32) -> Self {
@@ -238,6 +562,57 @@ impl ClientState {
         }
     }
 
+    /// reserves `reserved` pixels at each screen edge, e.g. for bars that
+    /// don't set a strut.
+    pub fn with_reserved(self, reserved: Strut) -> Self {
+        Self { reserved, ..self }
+    }
+
+    pub fn with_unfocused_border(self, border: Option<i32>) -> Self {
+        Self {
+            unfocused_border_size: border,
+            ..self
+        }
+    }
+
+    /// keeps floating clients within the work area, or at least `margin`
+    /// pixels of them if set.
+    pub fn with_constrain_floating(
+        self,
+        constrain_floating: bool,
+        margin: Option<i32>,
+    ) -> Self {
+        Self {
+            constrain_floating,
+            constrain_margin: margin,
+            ..self
+        }
+    }
+
+    pub fn with_attach_policy(self, attach_policy: AttachPolicy) -> Self {
+        Self {
+            attach_policy,
+            ..self
+        }
+    }
+
+    pub fn with_master_position(self, master_position: MasterPosition) -> Self {
+        Self {
+            master_position,
+            ..self
+        }
+    }
+
+    pub fn with_unfocusable_types(
+        self,
+        unfocusable_types: Vec<WindowType>,
+    ) -> Self {
+        Self {
+            unfocusable_types,
+            ..self
+        }
+    }
+
     pub fn with_screen_size(self, screen_size: Size<i32>) -> Self {
         Self {
             screen_size,
@@ -252,27 +627,282 @@ impl ClientState {
         }
     }
 
+    /// sets the layout of all virtual screens.
+    pub fn with_layout(mut self, layout: LayoutMode) -> Self {
+        self.virtual_screens
+            .iter_mut()
+            .for_each(|vs| vs.layout = layout);
+        self.default_layout = layout;
+
+        self
+    }
+
+    /**
+    Sets the size of the screen, e.g. after a monitor was plugged in, resizes fullscreen clients
+    to cover it and re-tiles the current virtual screen.
+    */
+    pub fn set_screen_size(&mut self, screen_size: Size<i32>) {
+        self.screen_size = screen_size;
+
+        self.clients
+            .values_mut()
+            .chain(self.floating_clients.values_mut())
+            .filter(|client| client.is_fullscreen())
+            .for_each(|client| {
+                client.position = Point::zero();
+                client.size = screen_size;
+            });
+
+        self.arrange_virtual_screen();
+    }
+
     pub fn get_border(&self) -> i32 {
         self.border_size
     }
 
-    #[allow(dead_code)]
-    pub fn set_border_mut(&mut self, new: i32) {
-        self.border_size = new;
+    /**
+    Sets the border width of focused clients, clamped to 0, and re-tiles the current virtual
+    screen so that the tiles account for the new width. Clients pick up the width the next
+    time their border is updated, see `update_border`.
+    */
+    pub fn set_border(&mut self, border: i32) {
+        self.border_size = border.max(0);
+
+        self.arrange_virtual_screen();
+    }
+
+    /// returns the border width of the client `key`, which depends on
+    /// whether it is focused. fullscreen clients, docks and desktops have no
+    /// border.
+    pub fn border_of<K>(&self, key: &K) -> i32
+    where
+        K: ClientKey,
+    {
+        match self.get(key).into_option() {
+            Some(c) if c.is_fullscreen() || c.is_fixed() => 0,
+            _ if self.is_focused(key) => self.border_size,
+            _ => self.unfocused_border_size.unwrap_or(self.border_size),
+        }
+    }
+
+    /// stores the border width of the client `key` as given by `border_of`
+    /// and returns it if it changed.
+    pub fn update_border<K>(&mut self, key: &K) -> Option<i32>
+    where
+        K: ClientKey,
+    {
+        let border = self.border_of(key);
+
+        self.get_mut(key)
+            .into_option()
+            .filter(|client| client.border != border)
+            .map(|client| {
+                client.border = border;
+                border
+            })
+    }
+
+    /**
+    Marks the current geometry of the client `key` as applied to its window and returns `true`
+    if it differs from the one applied before, i.e. the window has to be reconfigured.
+    */
+    pub fn update_geometry<K>(&mut self, key: &K) -> bool
+    where
+        K: ClientKey,
+    {
+        match self.get_mut(key).into_option() {
+            Some(client) => {
+                let geometry = Some((client.position, client.size));
+                let changed = client.applied != geometry;
+                client.applied = geometry;
+
+                changed
+            }
+            None => false,
+        }
+    }
+
+    /// forces the window of the client `key` to be reconfigured by the next
+    /// arrange, e.g. after it was moved off screen to hide it.
+    pub fn invalidate_geometry<K>(&mut self, key: &K)
+    where
+        K: ClientKey,
+    {
+        if let Some(client) = self.get_mut(key).into_option() {
+            client.applied = None;
+        }
+    }
+
+    /// returns the space reserved by all clients' struts, or an empty strut
+    /// if struts are disabled.
+    pub fn get_struts(&self) -> Strut {
+        if self.struts_enabled {
+            self.iter_all_clients()
+                .filter_map(|(_, c)| c.strut)
+                .fold(Strut::default(), Strut::max)
+        } else {
+            Strut::default()
+        }
+    }
+
+    /// returns the position and size of the area windows are tiled in, that
+    /// is the screen without the space reserved by struts or by the
+    /// configured margins.
+    pub fn get_work_area(&self) -> Rect {
+        let strut = self.get_struts().max(self.reserved);
+
+        Rect::new(
+            Point::new(strut.left, strut.top),
+            Size::new(
+                self.screen_size.width - strut.left - strut.right,
+                self.screen_size.height - strut.top - strut.bottom,
+            ),
+        )
+    }
+
+    /**
+    Returns `position` for the client `key`, snapped to the edges of the work area or of other
+    visible floating clients if any of its edges is within `distance` of them.
+    A `distance` of 0 disables snapping.
+    */
+    pub fn snap_position<K>(
+        &self,
+        key: &K,
+        position: Point<i32>,
+        distance: i32,
+    ) -> Point<i32>
+    where
+        K: ClientKey,
+    {
+        // snap the outer edges of clients, including their borders
+        let border = Size::new(self.border_size * 2, self.border_size * 2);
+
+        let size = match self.get(key).into_option() {
+            Some(client) if distance > 0 => client.size + border,
+            _ => return position,
+        };
+
+        let mut edges = vec![self.get_work_area()];
+
+        edges.extend(
+            self.floating_clients
+                .iter()
+                .filter(|&(&k, _)| k != key.key() && self.is_client_visible(&k))
+                .map(|(_, client)| {
+                    Rect::new(client.position, client.size + border)
+                }),
+        );
+
+        // offset to the nearest edge within `distance` along one axis
+        let snap = |start: i32, len: i32, edges: &[i32]| {
+            edges
+                .iter()
+                .flat_map(|&edge| [edge - start, edge - (start + len)])
+                .filter(|offset| offset.abs() <= distance)
+                .min_by_key(|offset| offset.abs())
+                .unwrap_or(0)
+        };
+
+        let xs = edges
+            .iter()
+            .flat_map(|edge| [edge.origin.x, edge.right()])
+            .collect::<Vec<_>>();
+        let ys = edges
+            .iter()
+            .flat_map(|edge| [edge.origin.y, edge.bottom()])
+            .collect::<Vec<_>>();
+
+        Point::new(
+            position.x + snap(position.x, size.width, &xs),
+            position.y + snap(position.y, size.height, &ys),
+        )
+    }
+
+    /**
+    Moves the floating client `key` back into the work area if constraining floating clients is
+    enabled, so that either all of it or at least the configured margin is within it.
+    Fullscreen clients, docks and desktops are exempt.
+    Returns `true` if the client was moved.
+    */
+    pub fn constrain_to_work_area<K>(&mut self, key: &K) -> bool
+    where
+        K: ClientKey,
+    {
+        if !self.constrain_floating {
+            return false;
+        }
+
+        let area = self.get_work_area();
+        let border = self.border_size * 2;
+        let margin = self.constrain_margin;
+
+        // range of valid origins along one axis
+        let clamp = |start: i32, len: i32, area_start: i32, area_len: i32| {
+            let (min, max) = match margin {
+                Some(margin) => {
+                    (area_start - len + margin, area_start + area_len - margin)
+                }
+                None => (area_start, area_start + area_len - len),
+            };
+
+            start.min(max).max(min)
+        };
+
+        match self.get_mut(key) {
+            ClientEntry::Floating(client)
+                if !client.is_fullscreen() && !client.is_fixed() =>
+            {
+                let position = Point::new(
+                    clamp(
+                        client.position.x,
+                        client.size.width + border,
+                        area.origin.x,
+                        area.size.width,
+                    ),
+                    clamp(
+                        client.position.y,
+                        client.size.height + border,
+                        area.origin.y,
+                        area.size.height,
+                    ),
+                );
+
+                let moved = position != client.position;
+                client.position = position;
+
+                moved
+            }
+            _ => false,
+        }
+    }
+
+    /// toggles whether the space reserved by struts is kept free and
+    /// rearranges the tiling.
+    pub fn toggle_struts(&mut self) {
+        self.struts_enabled = !self.struts_enabled;
+
+        self.arrange_virtual_screen();
     }
 
     pub fn insert(&mut self, mut client: Client) -> Option<&Client> {
         let key = client.key();
 
         match client.window_type {
-            // idk how to handle docks and desktops, for now they float innit
+            // docks and desktops keep their own geometry
+            WindowType::Dock | WindowType::Desktop => {
+                self.floating_clients.insert(key, client);
+            }
             WindowType::Splash
             | WindowType::Dialog
             | WindowType::Utility
             | WindowType::Menu
-            | WindowType::Toolbar
-            | WindowType::Dock
-            | WindowType::Desktop => {
+            | WindowType::Toolbar => {
+                let area = self.get_work_area();
+                let border =
+                    Size::new(self.border_size * 2, self.border_size * 2);
+
+                client.size = client.size.clamp(area.size - border);
+
                 if let Some(parent) = client
                     .parent_window
                     .and_then(|window| self.get(&window).into_option())
@@ -288,16 +918,28 @@ impl ClientState {
                     };
                 }
 
-                client.size = client.size.clamp(
-                    self.screen_size
-                        - Size::new(self.border_size * 2, self.border_size * 2),
-                );
+                // keep dialogs out from under docks, dialogs as large as the
+                // work area end up covering it exactly. constraining floating
+                // clients may allow them to stick out by a margin instead.
+                if !self.constrain_floating {
+                    client.position = area
+                        .fit(&Rect::new(client.position, client.size + border))
+                        .origin;
+                }
 
                 self.floating_clients.insert(key, client);
+                self.constrain_to_work_area(&key);
             }
             WindowType::Normal => {
                 self.clients.insert(key, client);
-                self.virtual_screens.get_mut_current().insert(&key);
+
+                let vs = self.virtual_screens.get_mut_current();
+                vs.insert(&key, self.attach_policy);
+
+                // new clients would be hidden behind the zoomed one
+                if vs.zoomed.is_some() {
+                    vs.zoomed = Some(key);
+                }
             }
         }
 
@@ -318,7 +960,35 @@ impl ClientState {
             }
         }
 
+        // give the slot back to the terminal this client swallowed
+        if let Some(terminal) =
+            self.get(key).into_option().and_then(|c| c.swallowed)
+        {
+            if self.virtual_screens.iter().any(|vs| vs.contains(key)) {
+                self.virtual_screens
+                    .iter_mut()
+                    .for_each(|vs| vs.replace(&key.key(), &terminal));
+            } else if self.contains(&terminal) {
+                // the client was floated or minimized and holds no slot
+                self.virtual_screens
+                    .get_mut_current()
+                    .insert(&terminal, self.attach_policy);
+            }
+        }
+
+        self.clients
+            .values_mut()
+            .chain(self.floating_clients.values_mut())
+            .filter(|c| c.swallowed == Some(key.key()))
+            .for_each(|c| c.swallowed = None);
+
         self.remove_from_virtual_screens(key);
+        self.virtual_screens
+            .iter_mut()
+            .filter(|vs| vs.focused == Some(key.key()))
+            .for_each(|vs| vs.focused = None);
+        self.minimized.retain(|&k| k != key.key());
+        self.urgent.retain(|&k| k != key.key());
 
         self.clients.remove(&key.key());
         self.floating_clients.remove(&key.key());
@@ -362,6 +1032,20 @@ impl ClientState {
         self.iter_floating().filter(|&(_, c)| c.has_parent_window())
     }
 
+    /// returns the clients that are transient for `parent`, e.g. its dialogs.
+    pub fn iter_transients_for<K>(
+        &self,
+        parent: &K,
+    ) -> impl Iterator<Item = (&u64, &Client)>
+    where
+        K: ClientKey,
+    {
+        let parent = parent.key();
+
+        self.iter_transient()
+            .filter(move |&(_, c)| c.parent_window == Some(parent))
+    }
+
     pub fn iter_by_window_type(
         &self,
         window_type: WindowType,
@@ -375,11 +1059,94 @@ impl ClientState {
             .filter(move |&(k, _)| self.is_client_visible(k))
     }
 
-    #[allow(dead_code)]
+    /**
+    Iterates over the visible clients in the order they are stacked, from bottom to top:
+    desktops, tiled master clients, tiled aux clients, floating clients, transient clients,
+    fullscreen clients and finally pinned floating clients.
+    */
+    pub fn iter_render_order(&self) -> impl Iterator<Item = &Client> {
+        let (master, aux) = self.virtual_screens.current_stacks();
+
+        let floating = self
+            .iter_floating_visible()
+            .filter(|(_, c)| !c.has_parent_window())
+            .map(|(k, _)| *k);
+        let transient = self
+            .iter_floating_visible()
+            .filter(|(_, c)| c.has_parent_window())
+            .map(|(k, _)| *k);
+
+        let mut keys = master
+            .into_iter()
+            .chain(aux)
+            .chain(floating)
+            .chain(transient)
+            .collect::<Vec<_>>();
+
+        // desktops are moved to the bottom and fullscreen clients to the top,
+        // keeping their order, only pinned floating clients go above them
+        keys.sort_by_key(|k| match self.get(k) {
+            ClientEntry::Floating(c) if c.is_pinned() => 3,
+            ClientEntry::Tiled(c) | ClientEntry::Floating(c)
+                if c.is_desktop() =>
+            {
+                0
+            }
+            ClientEntry::Tiled(c) | ClientEntry::Floating(c)
+                if c.is_fullscreen() =>
+            {
+                2
+            }
+            _ => 1,
+        });
+
+        keys.into_iter()
+            .filter_map(move |k| self.get(&k).into_option())
+    }
+
     pub fn iter_current_screen(&self) -> impl Iterator<Item = (&u64, &Client)> {
-        self.clients.iter().filter(move |&(k, _)| {
-            self.virtual_screens.get_current().contains(k)
-        })
+        self.clients
+            .iter()
+            .filter(move |&(k, _)| self.virtual_screens.is_viewed(k))
+    }
+
+    /// returns the tiled clients on the `n`th virtual screen, floating clients
+    /// are shown on every virtual screen and never included.
+    pub fn iter_workspace(
+        &self,
+        n: usize,
+    ) -> impl Iterator<Item = (&u64, &Client)> {
+        self.virtual_screens
+            .screens
+            .get(n)
+            .into_iter()
+            .flat_map(|vs| vs.master.iter().chain(vs.aux.iter()))
+            .filter_map(move |k| self.clients.get_key_value(k))
+    }
+
+    /**
+    Iterates over all clients in the order a window switcher lists them: the tiled clients of
+    the current virtual screen in master-then-aux order, the floating clients, which are
+    shown on every virtual screen, and then the tiled clients of the other virtual screens in
+    order. Clients tagged on multiple virtual screens are only listed once, docks and desktops
+    are never listed. Window titles aren't tracked by the client state and have to be queried
+    from the backend.
+    */
+    pub fn iter_switch_order(&self) -> impl Iterator<Item = (&u64, &Client)> {
+        let current = self.virtual_screens.current_idx;
+        let others =
+            (0..self.virtual_screens.len()).filter(move |&n| n != current);
+
+        let floating = self.iter_floating().filter(|(_, c)| {
+            !c.is_desktop() && c.window_type != WindowType::Dock
+        });
+
+        let mut seen = BTreeSet::new();
+
+        self.iter_workspace(current)
+            .chain(floating)
+            .chain(others.flat_map(move |n| self.iter_workspace(n)))
+            .filter(move |&(k, _)| seen.insert(*k))
     }
 
     pub fn iter_master_stack(&self) -> impl Iterator<Item = (&u64, &Client)> {
@@ -398,10 +1165,14 @@ impl ClientState {
             .map(move |k| (k, self.get(k).unwrap()))
     }
 
-    fn is_client_visible<K>(&self, key: &K) -> bool
+    pub fn is_client_visible<K>(&self, key: &K) -> bool
     where
         K: ClientKey,
     {
+        if self.is_minimized(key) {
+            return false;
+        }
+
         match self.get(key) {
             ClientEntry::Floating(c) => {
                 if let Some(transient_for) = c.parent_window {
@@ -411,7 +1182,8 @@ impl ClientState {
                 }
             }
             ClientEntry::Tiled(_) => {
-                self.virtual_screens.get_current().contains(key)
+                self.virtual_screens.is_viewed(key)
+                    && self.zoomed().is_none_or(|zoomed| zoomed == key.key())
             }
             _ => false,
         }
@@ -451,6 +1223,36 @@ impl ClientState {
         }
     }
 
+    pub fn current_virtualscreen_index(&self) -> usize {
+        self.virtual_screens.current_idx
+    }
+
+    /// returns the virtual screen that was current before the current one.
+    pub fn last_virtualscreen_index(&self) -> Option<usize> {
+        self.virtual_screens.last_idx
+    }
+
+    pub fn num_virtualscreens(&self) -> usize {
+        self.virtual_screens.len()
+    }
+
+    /**
+    Returns how many clients each virtual screen holds and whether any of them is urgent,
+    e.g. for a status bar to mark occupied virtual screens.
+    */
+    pub fn workspace_occupancy(&self) -> Vec<WorkspaceInfo> {
+        (0..self.virtual_screens.len())
+            .map(|index| WorkspaceInfo {
+                index,
+                window_count: self.iter_workspace(index).count(),
+                is_current: index == self.virtual_screens.current_idx,
+                has_urgent: self
+                    .iter_workspace(index)
+                    .any(|(k, _)| self.is_urgent(k)),
+            })
+            .collect()
+    }
+
     pub fn go_to_nth_virtualscreen(&mut self, n: usize) {
         self.virtual_screens.go_to_nth(n);
 
@@ -477,19 +1279,87 @@ impl ClientState {
         self.arrange_virtual_screen();
     }
 
-    pub fn set_fullscreen<K>(&mut self, key: &K, fullscreen: bool) -> bool
+    /**
+    Moves a tiled client to virtual screen `n`, removing it from all other virtual screens.
+    Returns `true` if the client was moved, floating and minimized clients are not on any
+    virtual screen and are left alone.
+    */
+    pub fn move_to_virtualscreen<K>(&mut self, key: &K, n: usize) -> bool
     where
         K: ClientKey,
     {
-        self.get(key)
-            .into_option()
-            .map(|client| client.is_fullscreen() != fullscreen)
-            .unwrap_or(false)
-            .then(|| self.toggle_fullscreen(key))
-            .unwrap_or(false)
-    }
+        if !self.get(key).is_tiled() || self.is_minimized(key) {
+            return false;
+        }
 
-    /// returns `true` if window layout changed
+        let n = n.min(self.virtual_screens.len() - 1);
+
+        self.remove_from_virtual_screens(key);
+        self.virtual_screens.screens[n].insert(key, self.attach_policy);
+
+        self.arrange_virtual_screen();
+
+        true
+    }
+
+    /// views virtual screen `n` alongside the current one, or stops viewing it.
+    pub fn toggle_nth_virtualscreen(&mut self, n: usize) {
+        self.virtual_screens.toggle_view(n);
+
+        self.arrange_virtual_screen();
+    }
+
+    /**
+    Adds a tiled client to virtual screen `n`, or removes it if it is already on `n` and
+    also on another virtual screen. Returns `true` if the client's virtual screens changed.
+    */
+    pub fn toggle_tag<K>(&mut self, key: &K, n: usize) -> bool
+    where
+        K: ClientKey,
+    {
+        if !self.get(key).is_tiled() || self.is_minimized(key) {
+            return false;
+        }
+
+        let n = n.min(self.virtual_screens.len() - 1);
+        let tags = self
+            .virtual_screens
+            .iter()
+            .filter(|vs| vs.contains(key))
+            .count();
+
+        let vs = &mut self.virtual_screens.screens[n];
+
+        let changed = if !vs.contains(key) {
+            vs.insert(key, self.attach_policy);
+            true
+        } else if tags > 1 {
+            vs.remove(key);
+            true
+        } else {
+            false
+        };
+
+        if changed {
+            self.arrange_virtual_screen();
+        }
+
+        changed
+    }
+
+    pub fn set_fullscreen<K>(&mut self, key: &K, fullscreen: bool) -> bool
+    where
+        K: ClientKey,
+    {
+        self.get(key)
+            .into_option()
+            .map(|client| client.is_fullscreen() != fullscreen)
+            .unwrap_or(false)
+            .then(|| self.toggle_fullscreen(key))
+            .unwrap_or(false)
+    }
+
+    /// returns `true` if window layout changed
     pub fn toggle_fullscreen<K>(&mut self, key: &K) -> bool
     where
         K: ClientKey,
@@ -507,14 +1377,30 @@ impl ClientState {
         K: ClientKey,
     {
         let fullscreen_size = self.screen_size;
+        let floating = self.get(key).is_floating();
 
         self.get_mut(key).into_option().map(|client| {
             if client.toggle_fullscreen() {
+                // tiled clients keep the geometry they had when last floating
+                if floating {
+                    client.saved_geometry =
+                        Some((client.position, client.size));
+                }
+
                 client.size = fullscreen_size;
                 client.position = Point::zero();
 
                 true
             } else {
+                // tiled clients are placed by the next arrange
+                if floating {
+                    if let Some((position, size)) = client.saved_geometry.take()
+                    {
+                        client.position = position;
+                        client.size = size;
+                    }
+                }
+
                 false
             }
         })
@@ -554,6 +1440,75 @@ impl ClientState {
         }
     }
 
+    /**
+    Floats all tiled clients on the current virtual screen, or tiles the clients floated this way
+    again in their previous order. Clients tiled in the meantime are kept after them.
+    */
+    pub fn toggle_all_floating(&mut self) {
+        match self.virtual_screens.get_mut_current().floated.take() {
+            Some(floated) => {
+                let mut tiled = Vec::with_capacity(floated.len());
+
+                for key in floated {
+                    // skip clients that were closed, minimized or made
+                    // fullscreen in the meantime
+                    let tileable = self
+                        .floating_clients
+                        .get(&key)
+                        .map(|c| {
+                            !c.is_fullscreen()
+                                && c.window_type == WindowType::Normal
+                        })
+                        .unwrap_or(false);
+
+                    if tileable && !self.minimized.contains(&key) {
+                        if let Some(client) = self.floating_clients.remove(&key)
+                        {
+                            self.clients.insert(key, client);
+                            tiled.push(key);
+                        }
+                    }
+                }
+
+                let vs = self.virtual_screens.get_mut_current();
+                tiled.append(&mut vs.master);
+                tiled.append(&mut vs.aux);
+                vs.master = tiled;
+                vs.refresh();
+            }
+            None => {
+                let vs = self.virtual_screens.get_current();
+                let floated = vs
+                    .master
+                    .iter()
+                    .chain(vs.aux.iter())
+                    .cloned()
+                    .filter(|key| {
+                        self.clients
+                            .get(key)
+                            .map(|c| !c.is_fullscreen())
+                            .unwrap_or(false)
+                    })
+                    .collect::<Vec<_>>();
+
+                if floated.is_empty() {
+                    return;
+                }
+
+                for &key in floated.iter() {
+                    if let Some(client) = self.clients.remove(&key) {
+                        self.floating_clients.insert(key, client);
+                        self.remove_from_virtual_screens(&key);
+                    }
+                }
+
+                self.virtual_screens.get_mut_current().floated = Some(floated);
+            }
+        }
+
+        self.arrange_virtual_screen();
+    }
+
     /**
     This function invalidates the tiling, call `arrange_clients` to fix it again (it doesn't do it
     automatically since xlib has to move and resize all windows anyways).
@@ -577,17 +1532,29 @@ impl ClientState {
             let floating_client = self.floating_clients.remove(&key);
 
             match (client, floating_client) {
-                (Some(client), None) => {
+                (Some(mut client), None) => {
+                    if let Some((position, size)) = client.saved_geometry.take()
+                    {
+                        client.position = position;
+                        client.size = size;
+                    }
+
                     self.floating_clients.insert(key, client);
                     self.remove_from_virtual_screens(&key);
                 }
-                (None, Some(floating_client)) => {
+                (None, Some(mut floating_client)) => {
                     // transient clients cannot be tiled
                     // only normal windows can be tiled
                     match floating_client.window_type {
                         WindowType::Normal => {
+                            floating_client.saved_geometry = Some((
+                                floating_client.position,
+                                floating_client.size,
+                            ));
                             self.clients.insert(key, floating_client);
-                            self.virtual_screens.get_mut_current().insert(&key);
+                            self.virtual_screens
+                                .get_mut_current()
+                                .insert(&key, self.attach_policy);
                         }
                         _ => {
                             self.floating_clients.insert(key, floating_client);
@@ -606,6 +1573,364 @@ impl ClientState {
         }
     }
 
+    /**
+    Tiles a floating client into the stack of the tiled client under `point`, at that
+    client's position in the stack. Returns `true` if the client was tiled, does nothing
+    if no tiled client is under `point` or the client can't be tiled.
+    If this function returns `true` you have to call `arrange_clients` after.
+    */
+    pub fn tile_at<K>(&mut self, key: &K, point: Point<i32>) -> bool
+    where
+        K: ClientKey,
+    {
+        let key = key.key();
+
+        match self.floating_clients.get(&key) {
+            Some(client)
+                if client.window_type == WindowType::Normal
+                    && !client.is_fullscreen() => {}
+            _ => return false,
+        }
+
+        let hit = |k: &u64| {
+            self.clients
+                .get(k)
+                .map(|client| client.contains_point(point))
+                .unwrap_or(false)
+        };
+
+        let vs = self.virtual_screens.get_current();
+        let target = vs
+            .master
+            .iter()
+            .position(hit)
+            .map(|index| (true, index))
+            .or_else(|| {
+                vs.aux.iter().position(hit).map(|index| (false, index))
+            });
+
+        match target {
+            Some((in_master, index)) => {
+                if let Some(client) = self.floating_clients.remove(&key) {
+                    self.clients.insert(key, client);
+                    self.virtual_screens
+                        .get_mut_current()
+                        .insert_at(&key, in_master, index);
+                }
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    /**
+    Returns the visible client whose center is nearest to the center of `key` in `direction`,
+    ignoring clients whose center lies behind it. Clients that can't be focused are never returned.
+    */
+    pub fn closest_client_in_direction<K>(
+        &self,
+        key: &K,
+        direction: Direction,
+    ) -> Option<ClientRef>
+    where
+        K: ClientKey,
+    {
+        let from = self.get(key).into_option()?.rect().center();
+
+        self.iter_visible()
+            .filter(|&(&k, _)| k != key.key() && self.is_focusable(&k))
+            .filter_map(|(&k, c)| {
+                let to = c.rect().center();
+                let (dx, dy) = (to.x - from.x, to.y - from.y);
+
+                let ahead = match direction {
+                    Direction::West(_) => dx < 0,
+                    Direction::East(_) => dx > 0,
+                    Direction::North(_) => dy < 0,
+                    Direction::South(_) => dy > 0,
+                };
+
+                ahead.then_some((k, dx * dx + dy * dy))
+            })
+            .min_by_key(|&(_, distance)| distance)
+            .map(|(k, _)| k)
+    }
+
+    /**
+    Moves a floating client `step` pixels in `direction`, keeping it within the work area.
+    Returns `true` if the client was moved, does nothing for tiled or fullscreen clients.
+    */
+    pub fn nudge_floating<K>(
+        &mut self,
+        key: &K,
+        direction: Direction,
+        step: i32,
+    ) -> bool
+    where
+        K: ClientKey,
+    {
+        let area = self.get_work_area();
+        let border = self.border_size;
+
+        match self.get_mut(key) {
+            ClientEntry::Floating(client) if !client.is_fullscreen() => {
+                let delta = match direction {
+                    Direction::West(n) => Point::new(-step * n as i32, 0),
+                    Direction::East(n) => Point::new(step * n as i32, 0),
+                    Direction::North(n) => Point::new(0, -step * n as i32),
+                    Direction::South(n) => Point::new(0, step * n as i32),
+                };
+
+                let max = Point::new(area.right(), area.bottom())
+                    - Point::new(client.size.width, client.size.height)
+                    - Point::new(border * 2, border * 2);
+                let position = client.position + delta;
+
+                client.position = Point::new(
+                    position.x.min(max.x).max(area.origin.x),
+                    position.y.min(max.y).max(area.origin.y),
+                );
+
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /**
+    Resizes a floating client by `step` pixels in `direction`, east and south grow it, west and north shrink it.
+    Returns `true` if the client was resized, does nothing for tiled or fullscreen clients.
+    */
+    pub fn resize_floating<K>(
+        &mut self,
+        key: &K,
+        direction: Direction,
+        step: i32,
+    ) -> bool
+    where
+        K: ClientKey,
+    {
+        match self.get_mut(key) {
+            ClientEntry::Floating(client) if !client.is_fullscreen() => {
+                let delta = match direction {
+                    Direction::West(n) => Size::new(-step * n as i32, 0),
+                    Direction::East(n) => Size::new(step * n as i32, 0),
+                    Direction::North(n) => Size::new(0, -step * n as i32),
+                    Direction::South(n) => Size::new(0, step * n as i32),
+                };
+
+                let size = client.size + delta;
+                client.size = client.constrain_aspect(Size::new(
+                    size.width.max(1),
+                    size.height.max(1),
+                ));
+
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /**
+    Pins a client above all other clients, including fullscreen clients, or unpins it.
+    The pin only takes effect while the client is floating. Returns `true` if the client is now pinned.
+    */
+    pub fn toggle_pin<K>(&mut self, key: &K) -> bool
+    where
+        K: ClientKey,
+    {
+        self.get_mut(key)
+            .into_option()
+            .map(|client| {
+                client.pinned = !client.pinned;
+                client.pinned
+            })
+            .unwrap_or(false)
+    }
+
+    /**
+    Zooms a tiled client on the current virtual screen so that it covers the whole tiling
+    area while the other tiled clients are hidden, or restores the split if it is zoomed.
+    The client stays tiled and keeps its place in the stack. Returns `true` if the client
+    is now zoomed.
+    */
+    pub fn toggle_zoom<K>(&mut self, key: &K) -> bool
+    where
+        K: ClientKey,
+    {
+        let key = key.key();
+        let vs = self.virtual_screens.get_mut_current();
+
+        if !vs.contains(&key) {
+            return false;
+        }
+
+        vs.zoomed = if vs.zoomed == Some(key) {
+            None
+        } else {
+            Some(key)
+        };
+        let zoomed = vs.zoomed.is_some();
+
+        self.arrange_virtual_screen();
+        zoomed
+    }
+
+    /// zooms `key` instead of the zoomed client if it is one of its hidden
+    /// peers, so that the focused client is always the one shown.
+    fn zoom_follow_focus<K>(&mut self, key: &K)
+    where
+        K: ClientKey,
+    {
+        let vs = self.virtual_screens.get_mut_current();
+
+        if vs.zoomed.is_some_and(|zoomed| zoomed != key.key())
+            && vs.contains(key)
+        {
+            vs.zoomed = Some(key.key());
+            self.arrange_virtual_screen();
+        }
+    }
+
+    /// returns the zoomed client of the current virtual screen, if any.
+    pub fn zoomed(&self) -> Option<ClientRef> {
+        self.virtual_screens.get_current().zoomed
+    }
+
+    pub fn is_minimized<K>(&self, key: &K) -> bool
+    where
+        K: ClientKey,
+    {
+        self.minimized.contains(&key.key())
+    }
+
+    /// returns the most recently minimized client, if any.
+    pub fn last_minimized(&self) -> Option<ClientRef> {
+        self.minimized.last().cloned()
+    }
+
+    pub fn is_urgent<K>(&self, key: &K) -> bool
+    where
+        K: ClientKey,
+    {
+        self.urgent.contains(&key.key())
+    }
+
+    /**
+    Marks a client as urgent or clears its urgency. Focusing a client clears its urgency.
+    Returns `true` if the urgency of the client changed.
+    */
+    pub fn set_urgent<K>(&mut self, key: &K, urgent: bool) -> bool
+    where
+        K: ClientKey,
+    {
+        if !self.contains(key) || self.is_urgent(key) == urgent {
+            return false;
+        }
+
+        if urgent {
+            self.urgent.push(key.key());
+        } else {
+            self.urgent.retain(|&k| k != key.key());
+        }
+
+        true
+    }
+
+    /// returns the most recently urgent client, if any.
+    pub fn find_urgent(&self) -> Option<ClientRef> {
+        self.urgent.last().cloned()
+    }
+
+    /// returns the index of the virtual screen `key` is tiled on, if any.
+    pub fn virtualscreen_index_of<K>(&self, key: &K) -> Option<usize>
+    where
+        K: ClientKey,
+    {
+        if self.virtual_screens.get_current().contains(key) {
+            return Some(self.virtual_screens.current_idx);
+        }
+
+        self.virtual_screens.iter().position(|vs| vs.contains(key))
+    }
+
+    /**
+    Minimizes or restores a client. Minimized clients are removed from the tiling and are not
+    visible, but stay managed so they can be restored; tiled clients are restored onto the
+    current virtual screen.
+    Returns `true` if the client is now minimized.
+    */
+    pub fn toggle_minimize<K>(&mut self, key: &K) -> bool
+    where
+        K: ClientKey,
+    {
+        if !self.contains(key) {
+            return false;
+        }
+
+        let key = key.key();
+
+        if self.is_minimized(&key) {
+            self.minimized.retain(|&k| k != key);
+
+            if self.get(&key).is_tiled() {
+                self.virtual_screens
+                    .get_mut_current()
+                    .insert(&key, self.attach_policy);
+            }
+        } else {
+            self.remove_from_virtual_screens(&key);
+            self.minimized.push(key);
+
+            if self.is_focused(&key) {
+                self.focused = None;
+            }
+        }
+
+        self.arrange_virtual_screen();
+
+        self.is_minimized(&key)
+    }
+
+    /**
+    Hides the tiled client `terminal` and puts `key` in its place on every virtual screen it
+    is on, e.g. for a program started from the terminal. The terminal is put back in place of
+    `key` once `key` is removed.
+    Returns `true` if `key` swallowed the terminal.
+    */
+    pub fn swallow<K>(&mut self, key: &K, terminal: &K) -> bool
+    where
+        K: ClientKey,
+    {
+        if key.key() == terminal.key()
+            || !self.get(key).is_tiled()
+            || self.virtualscreen_index_of(terminal).is_none()
+        {
+            return false;
+        }
+
+        self.virtual_screens
+            .iter_mut()
+            .filter(|vs| vs.contains(key))
+            .for_each(|vs| vs.remove(key));
+        self.virtual_screens
+            .iter_mut()
+            .for_each(|vs| vs.replace(terminal, key));
+
+        if let Some(client) = self.get_mut(key).into_option() {
+            client.swallowed = Some(terminal.key());
+        }
+
+        if self.is_focused(terminal) {
+            self.focused = None;
+        }
+
+        self.arrange_virtual_screen();
+
+        true
+    }
+
     pub fn update_window_type<K>(&mut self, key: &K, window_type: WindowType)
     where
         K: ClientKey,
@@ -625,19 +1950,27 @@ impl ClientState {
         K: ClientKey,
     {
         if self.contains(key) {
-            if let Some(vs) = self.get_mut_virtualscreen_for_client(key) {
-                vs.remove(key);
+            // a client can be on multiple virtual screens
+            self.virtual_screens
+                .iter_mut()
+                .filter(|vs| vs.contains(key))
+                .for_each(|vs| vs.remove(key));
 
-                // we removed a client so the layout changed, rearrange
-                self.arrange_virtual_screen();
-            }
+            // we removed a client so the layout changed, rearrange
+            self.arrange_virtual_screen();
         }
     }
 
+    /// returns the current virtual screen if it contains the client, or the
+    /// first virtual screen that does.
     fn get_virtualscreen_for_client<K>(&self, key: &K) -> Option<&VirtualScreen>
     where
         K: ClientKey,
     {
+        if self.virtual_screens.get_current().contains(key) {
+            return Some(self.virtual_screens.get_current());
+        }
+
         self.virtual_screens.iter().find_map(|vs| {
             if vs.contains(key) {
                 Some(vs)
@@ -654,6 +1987,10 @@ impl ClientState {
     where
         K: ClientKey,
     {
+        if self.virtual_screens.get_current().contains(key) {
+            return Some(self.virtual_screens.get_mut_current());
+        }
+
         self.virtual_screens.iter_mut().find_map(|vs| {
             if vs.contains(key) {
                 Some(vs)
@@ -680,6 +2017,22 @@ impl ClientState {
         }
     }
 
+    /// returns the stack the client is not on, on the virtual screen it is on.
+    pub fn get_other_stack_for_client<K>(&self, key: &K) -> Option<&Vec<u64>>
+    where
+        K: ClientKey,
+    {
+        self.get_virtualscreen_for_client(key).and_then(|vs| {
+            if vs.is_in_aux(key) {
+                Some(&vs.master)
+            } else if vs.is_in_master(key) {
+                Some(&vs.aux)
+            } else {
+                None
+            }
+        })
+    }
+
     /**
     focuses client `key` if it contains `key` and returns a reference to the  newly and the previously
     focused clients if any.
@@ -700,6 +2053,21 @@ impl ClientState {
         (new, old)
     }
 
+    /// returns `true` if focused and unfocused clients have different border
+    /// widths, so changing the focus changes the tiling.
+    pub fn has_focus_dependent_border(&self) -> bool {
+        self.unfocused_border_size
+            .is_some_and(|border| border != self.border_size)
+    }
+
+    /// tiled clients grow into the space of a border that shrinks when they
+    /// lose focus.
+    fn update_focus_borders(&mut self) {
+        if self.has_focus_dependent_border() {
+            self.arrange_virtual_screen();
+        }
+    }
+
     fn focus_client_inner<K>(
         &mut self,
         key: &K,
@@ -707,8 +2075,11 @@ impl ClientState {
     where
         K: ClientKey,
     {
-        // if `key` is not a valid entry into the client list, do nothing
-        if self.contains(key) {
+        // if `key` is not a valid entry into the client list or a client that
+        // can't be focused, do nothing
+        if self.is_focusable(key) {
+            self.urgent.retain(|&k| k != key.key());
+
             // check if we currently have a client focused
             match self.focused {
                 Some(focused) => {
@@ -722,6 +2093,11 @@ impl ClientState {
                         // and the previously focused client.
 
                         self.focused = Some(key.key());
+                        self.virtual_screens.get_mut_current().focused =
+                            Some(key.key());
+                        self.zoom_follow_focus(key);
+                        self.update_focus_borders();
+
                         (self.get(key), self.get(&focused))
                     }
                 }
@@ -731,6 +2107,11 @@ impl ClientState {
                     // just focus and return the client `key` references
 
                     self.focused = Some(key.key());
+                    self.virtual_screens.get_mut_current().focused =
+                        Some(key.key());
+                    self.zoom_follow_focus(key);
+                    self.update_focus_borders();
+
                     (self.get(key), ClientEntry::Vacant)
                 }
             }
@@ -741,22 +2122,26 @@ impl ClientState {
         }
     }
 
-    /**
-    sets `self.focused` to `None` and returns a reference to
-    the previously focused Client if any.
-    */
-    #[allow(dead_code)]
-    pub fn unfocus(&mut self) -> ClientEntry<&Client> {
-        match self.focused {
-            Some(focused) => {
-                self.focused = None;
-                self.get(&focused)
-            }
-            None => ClientEntry::Vacant,
-        }
+    /// returns the client last focused while the current virtual screen was
+    /// current if it is still visible.
+    pub fn last_focused_on_current(&self) -> Option<u64> {
+        self.virtual_screens
+            .get_current()
+            .focused
+            .filter(|key| self.is_client_visible(key))
+    }
+
+    /// returns `true` if `key` is a client whose window type may receive the
+    /// focus.
+    pub fn is_focusable<K>(&self, key: &K) -> bool
+    where
+        K: ClientKey,
+    {
+        self.get(key)
+            .into_option()
+            .is_some_and(|c| !self.unfocusable_types.contains(&c.window_type))
     }
 
-    #[allow(dead_code)]
     pub fn is_focused<K>(&self, key: &K) -> bool
     where
         K: ClientKey,
@@ -769,133 +2154,441 @@ impl ClientState {
 
     pub fn switch_stack_for_client<K>(&mut self, key: &K)
     where
-        K: ClientKey,
+        K: ClientKey,
+    {
+        if let Some(vs) = self.get_mut_virtualscreen_for_client(key) {
+            vs.switch_stack_for_client(key);
+
+            self.arrange_virtual_screen();
+        }
+    }
+
+    /**
+    Moves a tiled client to the head of the master stack of its virtual screen, unlike
+    `switch_stack_for_client` a master client is moved up instead of to aux.
+    Returns `true` if the stacks changed.
+    */
+    pub fn promote_to_master<K>(&mut self, key: &K) -> bool
+    where
+        K: ClientKey,
+    {
+        let promoted = self
+            .get_mut_virtualscreen_for_client(key)
+            .is_some_and(|vs| vs.promote_to_master(key));
+
+        if promoted {
+            self.arrange_virtual_screen();
+        }
+
+        promoted
+    }
+
+    /**
+    resizes and moves clients on the current virtual screen with `width` and `height` as
+    screen width and screen height.
+    Leaves `inner_gap` pixels between windows and `outer_gap` pixels at the screen edge.
+    */
+    pub fn arrange_virtual_screen(&mut self) {
+        match self.virtual_screens.get_current().layout {
+            LayoutMode::MasterStack => self.arrange_master_stack(),
+            LayoutMode::Spiral => self.arrange_spiral(),
+            LayoutMode::BottomStack => self.arrange_bottom_stack(),
+        }
+
+        if let Some(zoomed) = self.zoomed() {
+            let area = self.tiling_area();
+            self.place_tiled(std::iter::once((zoomed, area)));
+        }
+
+        // maximized tiled clients keep their place in the stack but cover
+        // the work area in the axes they are maximized in.
+        let area = self.get_work_area();
+        let border = self.border_size;
+        let (master, aux) = self.virtual_screens.current_stacks();
+
+        for key in master.iter().chain(aux.iter()) {
+            if let Some(client) = self.clients.get_mut(key) {
+                if !client.is_fullscreen() {
+                    client.apply_maximized(area, border);
+                }
+            }
+        }
+    }
+
+    /**
+    Sets the axes a client is maximized in, `None` leaves an axis unchanged. Floating clients
+    are resized immediately and get their previous geometry back when they are no longer
+    maximized, tiled clients are resized the next time they are arranged.
+    Returns `true` if the client's maximized state changed.
+    */
+    pub fn set_maximized<K>(
+        &mut self,
+        key: &K,
+        vert: Option<bool>,
+        horz: Option<bool>,
+    ) -> bool
+    where
+        K: ClientKey,
+    {
+        let area = self.get_work_area();
+        let border = self.border_size;
+
+        let changed = match self.get_mut(key) {
+            ClientEntry::Floating(client) => {
+                let old = client.maximized;
+                let new = (vert.unwrap_or(old.0), horz.unwrap_or(old.1));
+                client.maximized = new;
+
+                if old != new {
+                    // start from the unmaximized geometry so that an axis
+                    // that is no longer maximized is restored.
+                    match client.unmaximized {
+                        Some((position, size)) => {
+                            client.position = position;
+                            client.size = size;
+                        }
+                        None => {
+                            client.unmaximized =
+                                Some((client.position, client.size));
+                        }
+                    }
+
+                    if client.is_maximized() {
+                        client.apply_maximized(area, border);
+                    } else {
+                        client.unmaximized = None;
+                    }
+                }
+
+                old != new
+            }
+            ClientEntry::Tiled(client) => {
+                let old = client.maximized;
+                client.maximized =
+                    (vert.unwrap_or(old.0), horz.unwrap_or(old.1));
+
+                old != client.maximized
+            }
+            ClientEntry::Vacant => false,
+        };
+
+        if changed {
+            self.arrange_virtual_screen();
+        }
+
+        changed
+    }
+
+    /// sets the layout of the current virtual screen and rearranges it.
+    pub fn set_layout(&mut self, layout: LayoutMode) {
+        self.virtual_screens.get_mut_current().layout = layout;
+
+        self.arrange_virtual_screen();
+    }
+
+    pub fn get_layout(&self) -> LayoutMode {
+        self.virtual_screens.get_current().layout
+    }
+
+    /// switches the current virtual screen to the next layout and returns it.
+    pub fn cycle_layout(&mut self) -> LayoutMode {
+        use strum::IntoEnumIterator;
+
+        let current = self.get_layout();
+        let next = LayoutMode::iter()
+            .cycle()
+            .skip_while(|&layout| layout != current)
+            .nth(1)
+            .unwrap_or_default();
+
+        self.set_layout(next);
+
+        next
+    }
+
+    /**
+    returns the area tiles are placed in: the work area inset by `outer_gap`, minus the part
+    of `inner_gap` every tile is inset by in `place_tiled`, so that the gap at the screen edge
+    is `outer_gap` and the gap between tiles is `inner_gap`.
+    */
+    fn tiling_area(&self) -> Rect {
+        let area = self.get_work_area();
+        let lead = self.outer_gap - self.inner_gap / 2;
+
+        Rect::new(
+            area.origin + Point::new(lead, lead),
+            area.size
+                - Size::new(
+                    self.outer_gap * 2 - self.inner_gap,
+                    self.outer_gap * 2 - self.inner_gap,
+                ),
+        )
+    }
+
+    /// places tiled clients into their tiles, see `tiling_area`.
+    fn place_tiled<I>(&mut self, tiles: I)
+    where
+        I: IntoIterator<Item = (u64, Rect)>,
     {
-        if let Some(vs) = self.get_mut_virtualscreen_for_client(key) {
-            vs.switch_stack_for_client(key);
+        let gap = self.inner_gap;
+        let screen_size = self.screen_size;
 
-            self.arrange_virtual_screen();
+        for (key, tile) in tiles {
+            let border = self.border_of(&key);
+
+            if let Some(client) = self.clients.get_mut(&key) {
+                if client.is_fullscreen() {
+                    client.size = screen_size;
+                    client.position = Point::zero();
+                } else {
+                    client.position =
+                        tile.origin + Point::new(gap / 2, gap / 2);
+                    client.size = tile.size
+                        - Size::new(gap + border * 2, gap + border * 2);
+                }
+            }
         }
     }
 
     /**
-    resizes and moves clients on the current virtual screen with `width` and `height` as
-    screen width and screen height.
-    Optionally adds a gap between windows `gap.unwrap_or(0)` pixels wide.
+    arranges the clients of the current virtual screen in master-then-aux order as a spiral,
+    see `spiral_rects`.
     */
-    pub fn arrange_virtual_screen(&mut self) {
-        let gap = self.gap;
-        let (width, height) = self.screen_size.as_tuple();
+    fn arrange_spiral(&mut self) {
+        let area = self.tiling_area();
 
-        // should be fine to unwrap since we will always have at least 1 virtual screen
-        let vs = self.virtual_screens.get_mut_current();
-        // if aux is empty -> width : width / 2
+        let (master, aux) = self.virtual_screens.current_stacks();
+        let keys = master.into_iter().chain(aux).collect::<Vec<_>>();
 
-        let vs_width = width - gap * 2;
+        let rects = spiral_rects(area, keys.len())
+            .into_iter()
+            .map(|rect| self.master_position.orient(rect, area));
 
-        let master_position = Point::new(0, 0);
-        let master_window_size = {
-            let factor = if vs.aux.is_empty() {
-                1.0
-            } else {
-                self.master_size / 2.0
-            };
+        self.place_tiled(keys.into_iter().zip(rects).collect::<Vec<_>>());
+    }
 
-            let width = (vs_width as f32 * factor) as i32;
+    /**
+    arranges the master clients side by side on the top of the screen and the aux clients
+    side by side below them, `master_height` controls the height of the master row.
+    */
+    fn arrange_bottom_stack(&mut self) {
+        let Rect { origin, size } = self.tiling_area();
 
-            // make sure we dont devide by 0
-            // height is max height / number of clients in the stack
-            let height = match vs.master.len() as i32 {
-                0 => 1,
-                n => (height - gap * 2) / n,
-            };
+        let (master, aux) = self.virtual_screens.current_stacks();
 
-            Size::new(width, height)
+        let master_height = if aux.is_empty() {
+            size.height
+        } else {
+            (size.height as f32 * self.master_height / 2.0) as i32
         };
 
-        let aux_position = Point::new(master_window_size.width, 0);
-        let aux_window_size = {
-            let width = vs_width - master_window_size.width;
+        let rows = [
+            (
+                master,
+                Rect::new(origin, Size::new(size.width, master_height)),
+            ),
+            (
+                aux,
+                Rect::new(
+                    origin + Point::new(0, master_height),
+                    Size::new(size.width, size.height - master_height),
+                ),
+            ),
+        ];
+
+        let tiles = rows
+            .iter()
+            .flat_map(|(keys, row)| {
+                let splits =
+                    weighted_splits(row.size.width, &self.tile_weights(keys));
+
+                keys.iter().zip(splits).map(move |(&key, (x, width))| {
+                    (
+                        key,
+                        Rect::new(
+                            row.origin + Point::new(x, 0),
+                            Size::new(width, row.size.height),
+                        ),
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
 
-            // make sure we dont devide by 0
-            // height is max height / number of clients in the stack
-            let height = match vs.aux.len() as i32 {
-                0 => 1,
-                n => (height - gap * 2) / n,
-            };
+        self.place_tiled(tiles);
+    }
+
+    fn arrange_master_stack(&mut self) {
+        let area = self.tiling_area();
+        let Rect { origin, size } = area;
+        let position = self.master_position;
 
-            Size::new(width, height)
+        let (master, aux) = self.virtual_screens.current_stacks();
+
+        // if aux is empty -> width : width / 2
+        let master_width = if aux.is_empty() {
+            size.width
+        } else {
+            (size.width as f32 * self.master_size / 2.0) as i32
         };
 
-        fn calculate_window_dimensions(
-            screen_size: Size<i32>,
-            stack_size: Size<i32>,
-            stack_position: Point<i32>,
-            fullscreen: bool,
-            nth: i32,
-            gap: i32,
-            border: i32,
-        ) -> (Size<i32>, Point<i32>) {
-            if fullscreen {
-                let size = Size::new(screen_size.width, screen_size.height);
-                let pos = Point::new(0, 0);
-                (size, pos)
-            } else {
-                let size = Size::new(
-                    stack_size.width - gap * 2 - border * 2,
-                    stack_size.height - gap * 2 - border * 2,
-                );
-                let pos = Point::new(
-                    stack_position.x + gap * 2,
-                    stack_position.y + stack_size.height * nth + gap * 2,
+        let columns = [
+            (
+                master,
+                Rect::new(origin, Size::new(master_width, size.height)),
+            ),
+            (
+                aux,
+                Rect::new(
+                    origin + Point::new(master_width, 0),
+                    Size::new(size.width - master_width, size.height),
+                ),
+            ),
+        ];
+
+        let tiles = columns
+            .iter()
+            .flat_map(|(keys, column)| {
+                // the height of the stack is shared by the clients in it
+                // according to their weights
+                let splits = weighted_splits(
+                    column.size.height,
+                    &self.tile_weights(keys),
                 );
-                (size, pos)
-            }
+
+                keys.iter().zip(splits).map(move |(&key, (y, height))| {
+                    let tile = Rect::new(
+                        column.origin + Point::new(0, y),
+                        Size::new(column.size.width, height),
+                    );
+
+                    (key, position.orient(tile, area))
+                })
+            })
+            .collect::<Vec<_>>();
+
+        self.place_tiled(tiles);
+    }
+
+    /**
+    returns the virtual screen, stack and floating state of every client, tiled clients in
+    stack order followed by floating clients.
+    */
+    pub fn serialize_layout(&self) -> SavedLayout {
+        let tiled =
+            self.virtual_screens.iter().enumerate().flat_map(|(i, vs)| {
+                vs.master
+                    .iter()
+                    .map(move |k| (i, true, k))
+                    .chain(vs.aux.iter().map(move |k| (i, false, k)))
+            });
+
+        let tiled = tiled.filter_map(|(i, master, k)| {
+            self.clients.get(k).map(|c| LayoutEntry {
+                window: c.window,
+                virtual_screen: Some(i),
+                master,
+                floating: false,
+                position: c.position.as_tuple(),
+                size: c.size.as_tuple(),
+            })
+        });
+
+        let floating = self.floating_clients.values().map(|c| LayoutEntry {
+            window: c.window,
+            virtual_screen: None,
+            master: false,
+            floating: true,
+            position: c.position.as_tuple(),
+            size: c.size.as_tuple(),
+        });
+
+        SavedLayout {
+            current_virtual_screen: self.virtual_screens.current_idx,
+            clients: tiled.chain(floating).collect(),
         }
+    }
 
-        // Master
-        for (i, key) in vs.master.iter().enumerate() {
-            if let Some(client) = self.clients.get_mut(key) {
-                let (size, position) = calculate_window_dimensions(
-                    self.screen_size.into(),
-                    master_window_size,
-                    master_position,
-                    client.is_fullscreen(),
-                    i as i32,
-                    gap,
-                    self.border_size,
-                );
+    /**
+    restores the placement of all clients in `layout` that are managed, clients that aren't
+    part of `layout` keep their current placement.
+    */
+    pub fn restore_layout(&mut self, layout: &SavedLayout) {
+        // clients on multiple virtual screens have an entry for each of them
+        let mut restored = BTreeSet::new();
 
-                *client = Client {
-                    size: size.into(),
-                    position,
-                    ..*client
-                };
+        for entry in layout.clients.iter() {
+            let key = entry.window;
+
+            if !self.contains(&key) {
+                continue;
+            }
+
+            if entry.floating {
+                self.set_floating(&key);
+
+                if let ClientEntry::Floating(client) = self.get_mut(&key) {
+                    client.position = entry.position.into();
+                    client.size = entry.size.into();
+                }
+            } else if let Some(idx) = entry.virtual_screen {
+                self.set_tiled(&key);
+
+                // only normal windows can be tiled
+                if !self.get(&key).is_tiled() {
+                    continue;
+                }
+
+                if restored.insert(key) {
+                    self.remove_from_virtual_screens(&key);
+                }
+
+                let idx = idx.min(self.virtual_screens.len() - 1);
+                let vs = &mut self.virtual_screens.screens[idx];
+
+                if vs.contains(&key) {
+                    continue;
+                }
+
+                if entry.master {
+                    vs.master.push(key);
+                } else {
+                    vs.aux.push(key);
+                }
+
+                vs.refresh();
             }
         }
 
-        // Aux
-        for (i, key) in vs.aux.iter().enumerate() {
-            if let Some(client) = self.clients.get_mut(key) {
-                let (size, position) = calculate_window_dimensions(
-                    self.screen_size.into(),
-                    aux_window_size,
-                    aux_position,
-                    client.is_fullscreen(),
-                    i as i32,
-                    gap,
-                    self.border_size,
-                );
+        self.virtual_screens
+            .go_to_nth(layout.current_virtual_screen);
 
-                *client = Client {
-                    size: size.into(),
-                    position,
-                    ..*client
-                };
+        self.arrange_virtual_screen();
+    }
+
+    /**
+    Resets the master size and gap, and the layout and number of master clients of the
+    current virtual screen to the values the `ClientState` was built with.
+    */
+    pub fn reset_layout_params(&mut self) {
+        self.master_size = DEFAULT_MASTER_SIZE;
+        self.master_height = DEFAULT_MASTER_SIZE;
+        let (inner_gap, outer_gap) = self.default_gaps;
+        self.inner_gap = inner_gap;
+        self.outer_gap = outer_gap;
+
+        let vs = self.virtual_screens.get_mut_current();
+        vs.layout = self.default_layout;
+        vs.nmaster = 1;
+        vs.refresh();
+
+        let (master, aux) = self.virtual_screens.current_stacks();
+        for key in master.iter().chain(aux.iter()) {
+            if let Some(client) = self.clients.get_mut(key) {
+                client.weight = 1.0;
             }
         }
 
-        // Should have xlib send those changes back to the x server after this function
+        self.arrange_virtual_screen();
     }
 
     pub fn change_master_size(&mut self, delta: f32) {
@@ -904,6 +2597,148 @@ impl ClientState {
 
         self.arrange_virtual_screen();
     }
+
+    /// like `change_master_size`, for layouts that split the screen vertically.
+    pub fn change_master_height(&mut self, delta: f32) {
+        self.master_height = (self.master_height + delta).clamp(0.2, 1.8);
+
+        self.arrange_virtual_screen();
+    }
+
+    /// changes the number of clients on the master stack of the current
+    /// virtual screen by `delta`, keeping at least one client in master.
+    pub fn change_nmaster(&mut self, delta: i32) {
+        self.virtual_screens.get_mut_current().change_nmaster(delta);
+
+        self.arrange_virtual_screen();
+    }
+
+    pub fn get_nmaster(&self) -> usize {
+        self.virtual_screens.get_current().nmaster
+    }
+
+    /**
+    Grows or shrinks a tiled client's share of its stack by `delta`, relative to the default
+    weight of 1.0. Returns `true` if the client is tiled and its weight changed.
+    */
+    pub fn change_weight<K>(&mut self, key: &K, delta: f32) -> bool
+    where
+        K: ClientKey,
+    {
+        match self.get_mut(key) {
+            ClientEntry::Tiled(client) => {
+                let weight = (client.weight + delta).clamp(0.2, 5.0);
+                let changed = weight != client.weight;
+                client.weight = weight;
+
+                if changed {
+                    self.arrange_virtual_screen();
+                }
+
+                changed
+            }
+            _ => false,
+        }
+    }
+
+    fn tile_weights(&self, keys: &[u64]) -> Vec<f32> {
+        keys.iter()
+            .map(|k| self.clients.get(k).map(|c| c.weight).unwrap_or(1.0))
+            .collect()
+    }
+}
+
+/**
+splits `length` into one `(offset, length)` span per weight, each proportional to its weight.
+equal weights give every span `length / weights.len()`.
+*/
+pub fn weighted_splits(length: i32, weights: &[f32]) -> Vec<(i32, i32)> {
+    let total = weights.iter().sum::<f32>();
+    let mut offset = 0;
+
+    weights
+        .iter()
+        .map(|&weight| {
+            let span = (length as f32 * weight / total) as i32;
+            let split = (offset, span);
+            offset += span;
+
+            split
+        })
+        .collect()
+}
+
+/**
+splits `area` into `n` rectangles: every rectangle but the last takes half of the remaining
+space, alternating between splitting vertically and horizontally and rotating left, top,
+right, bottom so the rectangles spiral inwards.
+*/
+pub fn spiral_rects(area: Rect, n: usize) -> Vec<Rect> {
+    let mut rects = Vec::with_capacity(n);
+    let Rect {
+        origin: mut position,
+        mut size,
+    } = area;
+
+    for i in 0..n {
+        if i == n - 1 {
+            rects.push(Rect::new(position, size));
+            break;
+        }
+
+        let (rect, rest) = match i % 4 {
+            // take the left half
+            0 => {
+                let width = size.width / 2;
+                (
+                    (position, Size::new(width, size.height)),
+                    (
+                        position + Point::new(width, 0),
+                        Size::new(size.width - width, size.height),
+                    ),
+                )
+            }
+            // take the top half
+            1 => {
+                let height = size.height / 2;
+                (
+                    (position, Size::new(size.width, height)),
+                    (
+                        position + Point::new(0, height),
+                        Size::new(size.width, size.height - height),
+                    ),
+                )
+            }
+            // take the right half
+            2 => {
+                let width = size.width / 2;
+                (
+                    (
+                        position + Point::new(size.width - width, 0),
+                        Size::new(width, size.height),
+                    ),
+                    (position, Size::new(size.width - width, size.height)),
+                )
+            }
+            // take the bottom half
+            _ => {
+                let height = size.height / 2;
+                (
+                    (
+                        position + Point::new(0, size.height - height),
+                        Size::new(size.width, height),
+                    ),
+                    (position, Size::new(size.width, size.height - height)),
+                )
+            }
+        };
+
+        rects.push(rect.into());
+        position = rest.0;
+        size = rest.1;
+    }
+
+    rects
 }
 
 impl Default for VirtualScreen {
@@ -911,6 +2746,11 @@ impl Default for VirtualScreen {
         Self {
             master: Default::default(),
             aux: Default::default(),
+            nmaster: 1,
+            layout: LayoutMode::default(),
+            floated: None,
+            focused: None,
+            zoomed: None,
         }
     }
 }
@@ -937,11 +2777,30 @@ impl VirtualScreen {
         self.aux.contains(&key.key())
     }
 
-    fn insert<K>(&mut self, key: &K)
+    fn insert<K>(&mut self, key: &K, policy: AttachPolicy)
+    where
+        K: ClientKey,
+    {
+        match policy {
+            AttachPolicy::Master => self.master.insert(0, key.key()),
+            AttachPolicy::Aux => self.aux.insert(0, key.key()),
+            AttachPolicy::Bottom => self.aux.push(key.key()),
+        }
+
+        self.refresh();
+    }
+
+    /// inserts the client into the master or aux stack at `index`.
+    fn insert_at<K>(&mut self, key: &K, master: bool, index: usize)
     where
         K: ClientKey,
     {
-        self.aux.push(key.key());
+        let stack = if master {
+            &mut self.master
+        } else {
+            &mut self.aux
+        };
+        stack.insert(index.min(stack.len()), key.key());
 
         self.refresh();
     }
@@ -954,34 +2813,101 @@ impl VirtualScreen {
         self.master.retain(|k| *k != key);
         self.aux.retain(|k| *k != key);
 
+        if self.zoomed == Some(key) {
+            self.zoomed = None;
+        }
+
         self.refresh();
     }
 
+    /// puts `new` in the place of `old` on either stack, if `old` is on it.
+    fn replace<K>(&mut self, old: &K, new: &K)
+    where
+        K: ClientKey,
+    {
+        let (old, new) = (old.key(), new.key());
+
+        self.master
+            .iter_mut()
+            .chain(self.aux.iter_mut())
+            .filter(|k| **k == old)
+            .for_each(|k| *k = new);
+    }
+
+    /**
+    moves the client to the other stack by swapping it with a client from that
+    stack, so that the master stack always holds `nmaster` clients:
+    a master client swaps with the head of aux, an aux client swaps with the
+    last master client.
+    */
     fn switch_stack_for_client<K>(&mut self, key: &K)
     where
         K: ClientKey,
     {
-        match self.master.iter().position(|&k| k == key.key()) {
+        let key = key.key();
+
+        match self.master.iter().position(|&k| k == key) {
             Some(index) => {
-                self.aux.extend(self.master.drain(index..=index));
+                if !self.aux.is_empty() {
+                    std::mem::swap(&mut self.master[index], &mut self.aux[0]);
+                }
             }
             None => {
-                let index =
-                    self.aux.iter().position(|&k| k == key.key()).unwrap();
-                self.master.extend(self.aux.drain(index..=index));
+                if let Some(index) = self.aux.iter().position(|&k| k == key) {
+                    match self.master.last_mut() {
+                        Some(last) => {
+                            std::mem::swap(last, &mut self.aux[index])
+                        }
+                        None => self.master.push(self.aux.remove(index)),
+                    }
+                }
             }
         }
 
         self.refresh();
     }
 
+    /// moves the client to the head of the master stack, pushing the last
+    /// master client to the head of aux. returns `false` if it is already
+    /// the head of master or not on this screen.
+    fn promote_to_master<K>(&mut self, key: &K) -> bool
+    where
+        K: ClientKey,
+    {
+        let key = key.key();
+
+        if self.master.first() == Some(&key) || !self.contains(&key) {
+            return false;
+        }
+
+        self.master.retain(|&k| k != key);
+        self.aux.retain(|&k| k != key);
+        self.master.insert(0, key);
+
+        self.refresh();
+        true
+    }
+
+    fn change_nmaster(&mut self, delta: i32) {
+        self.nmaster = (self.nmaster as i32 + delta).max(1) as usize;
+
+        self.refresh();
+    }
+
     /**
-    if `self.master` is empty but `self.aux` has at least one client, drain from aux to master
+    rebalances the stacks so that the first `nmaster` clients are on the master stack and
+    the rest are on the aux stack, preserving their order.
     this ensures that if only 1 `Client` is on this `VirtualScreen` it will be on the master stack
     */
     fn refresh(&mut self) {
-        if self.master.is_empty() && !self.aux.is_empty() {
-            self.master.extend(self.aux.drain(..1));
+        let nmaster = self.nmaster.max(1);
+
+        if self.master.len() > nmaster {
+            let overflow = self.master.split_off(nmaster);
+            self.aux.splice(0..0, overflow);
+        } else if self.master.len() < nmaster && !self.aux.is_empty() {
+            let n = (nmaster - self.master.len()).min(self.aux.len());
+            self.master.extend(self.aux.drain(..n));
         }
     }
 }
@@ -994,10 +2920,61 @@ impl VirtualScreenStore {
         Self {
             screens,
             current_idx: 0,
+            current_indices: std::iter::once(0).collect(),
             last_idx: None,
         }
     }
 
+    fn current_indices(&self) -> impl Iterator<Item = &usize> {
+        self.current_indices.iter()
+    }
+
+    /// returns true if the client is on any of the viewed screens.
+    fn is_viewed<K>(&self, key: &K) -> bool
+    where
+        K: ClientKey,
+    {
+        self.current_indices()
+            .any(|&i| self.screens[i].contains(key))
+    }
+
+    /**
+    returns the master and aux stacks of all viewed screens: the stacks of the primary
+    screen, followed by the clients of the other viewed screens on the aux stack.
+    */
+    fn current_stacks(&self) -> (ClientRefs, ClientRefs) {
+        let current = self.get_current();
+        let master = current.master.clone();
+        let mut aux = current.aux.clone();
+
+        for &i in self.current_indices().filter(|&&i| i != self.current_idx) {
+            let vs = &self.screens[i];
+
+            for &key in vs.master.iter().chain(vs.aux.iter()) {
+                if !master.contains(&key) && !aux.contains(&key) {
+                    aux.push(key);
+                }
+            }
+        }
+
+        (master, aux)
+    }
+
+    /// adds or removes screen `n` from the viewed screens, the primary screen
+    /// is always viewed.
+    fn toggle_view(&mut self, n: usize) {
+        let n = n.min(self.screens.len() - 1);
+
+        if n != self.current_idx && !self.current_indices.remove(&n) {
+            self.current_indices.insert(n);
+        }
+    }
+
+    fn view_only_current(&mut self) {
+        self.current_indices.clear();
+        self.current_indices.insert(self.current_idx);
+    }
+
     fn get_current(&self) -> &VirtualScreen {
         &self.screens[self.current_idx]
     }
@@ -1032,6 +3009,7 @@ impl VirtualScreenStore {
         let b = self.current_idx % l;
 
         self.current_idx = ((b + l) - a) % l;
+        self.view_only_current();
 
         self.current_idx
     }
@@ -1044,6 +3022,7 @@ impl VirtualScreenStore {
         let b = self.current_idx % l;
 
         self.current_idx = ((b + l) + a) % l;
+        self.view_only_current();
 
         self.current_idx
     }
@@ -1052,6 +3031,7 @@ impl VirtualScreenStore {
         self.last_idx = Some(self.current_idx);
 
         self.current_idx = n.min(self.screens.len() - 1);
+        self.view_only_current();
 
         self.current_idx
     }
@@ -1124,3 +3104,622 @@ impl ClientEntry<&mut client::Client> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: i32, y: i32, w: i32, h: i32) -> Rect {
+        Rect::new(Point::new(x, y), Size::new(w, h))
+    }
+
+    #[test]
+    fn spiral_three_windows() {
+        let rects = spiral_rects(rect(0, 0, 100, 100), 3);
+
+        assert_eq!(
+            rects,
+            vec![
+                rect(0, 0, 50, 100),
+                rect(50, 0, 50, 50),
+                rect(50, 50, 50, 50)
+            ]
+        );
+    }
+
+    #[test]
+    fn spiral_four_windows() {
+        let rects = spiral_rects(rect(0, 0, 100, 100), 4);
+
+        assert_eq!(
+            rects,
+            vec![
+                rect(0, 0, 50, 100),
+                rect(50, 0, 50, 50),
+                rect(75, 50, 25, 50),
+                rect(50, 50, 25, 50)
+            ]
+        );
+    }
+
+    #[test]
+    fn tagged_client_visible_on_both_screens() {
+        let mut state = ClientState::new().with_virtualscreens(3);
+        state.insert(Client::new_default(1));
+        state.insert(Client::new_default(2));
+
+        assert!(state.toggle_tag(&1u64, 1));
+
+        state.go_to_nth_virtualscreen(1);
+        assert!(state.is_client_visible(&1u64));
+        assert!(!state.is_client_visible(&2u64));
+
+        // removing the last tag of a client does nothing
+        assert!(state.toggle_tag(&1u64, 0));
+        assert!(!state.toggle_tag(&1u64, 1));
+
+        state.go_to_nth_virtualscreen(2);
+        state.insert(Client::new_default(3));
+        state.go_to_nth_virtualscreen(0);
+        state.toggle_nth_virtualscreen(1);
+
+        assert!(state.is_client_visible(&1u64));
+        assert!(state.is_client_visible(&2u64));
+        assert!(!state.is_client_visible(&3u64));
+    }
+
+    #[test]
+    fn bottom_stack_master_height() {
+        let mut state = ClientState::new()
+            .with_screen_size((100, 100).into())
+            .with_layout(LayoutMode::BottomStack);
+        state.insert(Client::new_default(1));
+        state.insert(Client::new_default(2));
+        state.insert(Client::new_default(3));
+
+        state.change_master_height(0.2);
+
+        let master = state.get(&1u64).unwrap();
+        assert_eq!(master.size, Size::new(100, 60));
+
+        let aux = state.get(&3u64).unwrap();
+        assert_eq!(aux.rect(), rect(50, 60, 50, 40));
+    }
+
+    #[test]
+    fn inner_and_outer_gaps() {
+        let mut state = ClientState::new()
+            .with_screen_size((100, 100).into())
+            .with_gaps(4, 10);
+        state.insert(Client::new_default(1));
+        state.insert(Client::new_default(2));
+
+        let master = state.get(&1u64).unwrap();
+        let aux = state.get(&2u64).unwrap();
+
+        assert_eq!(master.rect(), rect(10, 10, 38, 80));
+        assert_eq!(aux.rect(), rect(52, 10, 38, 80));
+    }
+
+    #[test]
+    fn render_order_fullscreen_on_top() {
+        let mut state = ClientState::new();
+        state.insert(Client::new_default(1));
+        state.insert(Client::new_default(2));
+        state.insert(
+            Client::new_default(3).with_window_type(WindowType::Dialog),
+        );
+        state.insert(Client::new_default(4));
+        state.set_fullscreen(&1u64, true);
+
+        let order = state
+            .iter_render_order()
+            .map(|c| c.window)
+            .collect::<Vec<_>>();
+
+        assert_eq!(order, vec![2, 4, 3, 1]);
+    }
+
+    #[test]
+    fn maximized_floating_client_restores_geometry() {
+        let mut state = ClientState::new().with_screen_size((200, 100).into());
+        state.insert(
+            Client::new(1, (50, 40).into(), (10, 20).into())
+                .with_window_type(WindowType::Dialog),
+        );
+
+        assert!(state.set_maximized(&1u64, None, Some(true)));
+        let client = state.get(&1u64).unwrap();
+        assert_eq!(client.position, Point::new(0, 20));
+        assert_eq!(client.size, Size::new(200, 40));
+
+        assert!(state.set_maximized(&1u64, None, Some(false)));
+        let client = state.get(&1u64).unwrap();
+        assert_eq!(client.position, Point::new(10, 20));
+        assert_eq!(client.size, Size::new(50, 40));
+    }
+
+    #[test]
+    fn urgent_clients_cleared_on_focus() {
+        let mut state = ClientState::new().with_virtualscreens(2);
+        state.insert(Client::new_default(1));
+        state.go_to_nth_virtualscreen(1);
+        state.insert(Client::new_default(2));
+        state.go_to_nth_virtualscreen(0);
+
+        assert!(state.set_urgent(&2u64, true));
+        assert!(state.set_urgent(&1u64, true));
+        assert!(!state.set_urgent(&1u64, true));
+
+        // the most recently urgent client comes first
+        assert_eq!(state.find_urgent(), Some(1));
+        state.focus_client(&1u64);
+
+        assert_eq!(state.find_urgent(), Some(2));
+        assert_eq!(state.virtualscreen_index_of(&2u64), Some(1));
+        state.focus_client(&2u64);
+
+        assert_eq!(state.find_urgent(), None);
+    }
+
+    #[test]
+    fn snap_to_work_area_and_floating_clients() {
+        let mut state = ClientState::new().with_screen_size((100, 100).into());
+        state.insert(Client::new_default(1));
+        state.insert(Client::new_default(2));
+
+        for key in [1u64, 2] {
+            state.set_floating(&key);
+            state.get_mut(&key).unwrap().size = Size::new(20, 20);
+        }
+        state.get_mut(&2u64).unwrap().position = Point::new(40, 40);
+
+        assert_eq!(
+            state.snap_position(&1u64, Point::new(3, 50), 5),
+            Point::new(0, 50)
+        );
+        assert_eq!(
+            state.snap_position(&1u64, Point::new(77, 50), 5),
+            Point::new(80, 50)
+        );
+        // snaps to the right edge of client 2
+        assert_eq!(
+            state.snap_position(&1u64, Point::new(62, 30), 5),
+            Point::new(60, 30)
+        );
+        assert_eq!(
+            state.snap_position(&1u64, Point::new(62, 30), 0),
+            Point::new(62, 30)
+        );
+    }
+
+    #[test]
+    fn nudging_stays_within_work_area() {
+        let mut state = ClientState::new()
+            .with_screen_size((100, 100).into())
+            .with_border(0)
+            .with_reserved(Strut::new(0, 10, 20, 0));
+        state.insert(Client::new_default(1));
+        state.set_floating(&1u64);
+        if let Some(client) = state.get_mut(&1u64).into_option() {
+            client.position = Point::new(40, 40);
+            client.size = Size::new(30, 30);
+        }
+
+        assert!(state.nudge_floating(&1u64, Direction::North(1), 100));
+        assert!(state.nudge_floating(&1u64, Direction::East(1), 100));
+        assert_eq!(state.get(&1u64).unwrap().position, Point::new(60, 20));
+    }
+
+    #[test]
+    fn fullscreen_covers_struts() {
+        let mut state = ClientState::new().with_screen_size((100, 100).into());
+        state.insert(
+            Client::new_default(1)
+                .with_window_type(WindowType::Dock)
+                .with_strut(Some(Strut::new(0, 0, 20, 0))),
+        );
+        state.insert(Client::new_default(2));
+
+        assert_eq!(state.get_work_area(), rect(0, 20, 100, 80));
+        assert!(state.get(&2u64).unwrap().position.y >= 20);
+
+        assert!(state.set_fullscreen(&2u64, true));
+
+        let client = state.get(&2u64).unwrap();
+        assert_eq!(client.rect(), rect(0, 0, 100, 100));
+    }
+
+    #[test]
+    fn toggle_all_floating_restores_order() {
+        let mut state = ClientState::new();
+        state.insert(Client::new_default(1));
+        state.insert(Client::new_default(2));
+        state.insert(Client::new_default(3));
+
+        let stacks = |state: &ClientState| {
+            let vs = state.virtual_screens.get_current();
+            (vs.master.clone(), vs.aux.clone())
+        };
+        let before = stacks(&state);
+
+        state.toggle_all_floating();
+        assert!((1..=3).all(|k: u64| state.get(&k).is_floating()));
+        assert_eq!(stacks(&state), (vec![], vec![]));
+
+        state.insert(Client::new_default(4));
+        state.toggle_all_floating();
+
+        let (master, mut aux) = before;
+        aux.push(4);
+        assert_eq!(stacks(&state), (master, aux));
+    }
+
+    #[test]
+    fn docks_and_desktops_keep_their_place() {
+        let mut state = ClientState::new().with_screen_size((100, 100).into());
+        state.insert(Client::new_default(1));
+        state.insert(
+            Client::new_default(2)
+                .with_window_type(WindowType::Dock)
+                .with_position(Point::new(0, 90))
+                .with_size(Size::new(100, 10)),
+        );
+        state.insert(
+            Client::new_default(3)
+                .with_window_type(WindowType::Desktop)
+                .with_size(Size::new(100, 100)),
+        );
+
+        let dock = state.get(&2u64).unwrap();
+        assert_eq!(dock.rect(), rect(0, 90, 100, 10));
+
+        // desktops are stacked below everything else
+        let order = state.iter_render_order().map(|c| c.window);
+        assert_eq!(order.collect::<Vec<_>>(), vec![3, 1, 2]);
+
+        state.focus_client(&1u64);
+        state.focus_client(&2u64);
+        state.focus_client(&3u64);
+        assert!(state.is_focused(&1u64));
+    }
+
+    #[test]
+    fn closest_client_in_direction_of_grid() {
+        let mut state = ClientState::new().with_screen_size((100, 100).into());
+
+        // 1 2
+        // 3 4
+        for (key, x, y) in [(1u64, 0, 0), (2, 50, 0), (3, 0, 50), (4, 50, 50)] {
+            state.insert(Client::new_default(key));
+            state.set_floating(&key);
+
+            let client = state.get_mut(&key).unwrap();
+            client.position = Point::new(x, y);
+            client.size = Size::new(50, 50);
+        }
+
+        let closest = |from: u64, direction| {
+            state.closest_client_in_direction(&from, direction)
+        };
+
+        assert_eq!(closest(1, Direction::East(1)), Some(2));
+        assert_eq!(closest(1, Direction::South(1)), Some(3));
+        assert_eq!(closest(1, Direction::West(1)), None);
+        assert_eq!(closest(1, Direction::North(1)), None);
+        assert_eq!(closest(4, Direction::North(1)), Some(2));
+        assert_eq!(closest(4, Direction::West(1)), Some(3));
+    }
+
+    #[test]
+    fn swallowed_terminal_returns_on_remove() {
+        let mut state = ClientState::new().with_screen_size((100, 100).into());
+        state.insert(Client::new_default(1));
+        state.insert(Client::new_default(2));
+        state.insert(Client::new_default(3));
+
+        let stacks = |state: &ClientState| {
+            let vs = state.virtual_screens.get_current();
+            (vs.master.clone(), vs.aux.clone())
+        };
+        let before = stacks(&state);
+
+        assert!(!state.swallow(&4u64, &2u64));
+        state.insert(Client::new_default(4));
+        assert!(state.swallow(&4u64, &2u64));
+
+        assert!(!state.is_client_visible(&2u64));
+        assert_eq!(stacks(&state), (vec![1], vec![4, 3]));
+
+        state.remove(&4u64);
+        assert!(state.is_client_visible(&2u64));
+        assert_eq!(stacks(&state), before);
+
+        // a floating client holds no slot, the terminal is tiled again
+        state.insert(Client::new_default(4));
+        assert!(state.swallow(&4u64, &2u64));
+        state.set_floating(&4u64);
+        state.remove(&4u64);
+        assert!(state.is_client_visible(&2u64));
+        assert_eq!(stacks(&state), (vec![1], vec![3, 2]));
+    }
+
+    #[test]
+    fn floating_geometry_survives_tiling_and_fullscreen() {
+        let mut state = ClientState::new().with_screen_size((200, 100).into());
+        state.insert(Client::new_default(1));
+        state.set_floating(&1u64);
+
+        let client = state.get_mut(&1u64).unwrap();
+        (client.position, client.size) = rect(10, 20, 50, 40).into();
+
+        let geometry = |state: &ClientState| state.get(&1u64).unwrap().rect();
+
+        assert!(state.set_fullscreen(&1u64, true));
+        assert_eq!(geometry(&state), rect(0, 0, 200, 100));
+        assert!(state.set_fullscreen(&1u64, false));
+        assert_eq!(geometry(&state), rect(10, 20, 50, 40));
+
+        state.set_tiled(&1u64);
+        assert_ne!(geometry(&state), rect(10, 20, 50, 40));
+        state.set_fullscreen(&1u64, true);
+        state.set_fullscreen(&1u64, false);
+        state.set_floating(&1u64);
+        assert_eq!(geometry(&state), rect(10, 20, 50, 40));
+    }
+
+    #[test]
+    fn floating_clients_constrained_to_work_area() {
+        let mut state = ClientState::new()
+            .with_screen_size((100, 100).into())
+            .with_constrain_floating(true, None);
+        state.insert(
+            Client::new(1, (40, 40).into(), (80, -10).into())
+                .with_window_type(WindowType::Dialog),
+        );
+
+        let client = state.get(&1u64).unwrap();
+        assert_eq!(client.rect(), rect(60, 0, 40, 40));
+
+        let mut state = ClientState::new()
+            .with_screen_size((100, 100).into())
+            .with_constrain_floating(true, Some(10));
+        state.insert(
+            Client::new(1, (40, 40).into(), (95, -45).into())
+                .with_window_type(WindowType::Dialog),
+        );
+
+        // at least 10 pixels stay on the screen
+        let client = state.get(&1u64).unwrap();
+        assert_eq!(client.position, Point::new(90, -30));
+    }
+
+    #[test]
+    fn cycle_layout_wraps_around() {
+        let mut state = ClientState::new().with_virtualscreens(2);
+
+        assert_eq!(state.cycle_layout(), LayoutMode::Spiral);
+        assert_eq!(state.cycle_layout(), LayoutMode::BottomStack);
+        assert_eq!(state.cycle_layout(), LayoutMode::MasterStack);
+
+        // layouts are per virtual screen
+        state.cycle_layout();
+        state.go_to_nth_virtualscreen(1);
+        assert_eq!(state.get_layout(), LayoutMode::MasterStack);
+    }
+
+    #[test]
+    fn floating_resize_respects_aspect_ratio() {
+        let mut state = ClientState::new().with_screen_size((1000, 800).into());
+        state.insert(
+            Client::new(1, (100, 100).into(), (0, 0).into())
+                .with_aspect(Some((1.0, 2.0))),
+        );
+        state.insert(Client::new(2, (100, 100).into(), (0, 0).into()));
+
+        assert!(!state.resize_floating(&1u64, Direction::East(1), 20));
+
+        for key in [1u64, 2] {
+            state.set_floating(&key);
+            state.get_mut(&key).into_option().unwrap().size =
+                Size::new(100, 100);
+        }
+
+        // wider than 2:1 is clamped by shrinking the width
+        assert!(state.resize_floating(&1u64, Direction::East(6), 20));
+        assert_eq!(state.get(&1u64).unwrap().size, Size::new(200, 100));
+
+        // taller than 1:1 is clamped by shrinking the height
+        assert!(state.resize_floating(&1u64, Direction::West(6), 20));
+        assert!(state.resize_floating(&1u64, Direction::South(1), 20));
+        assert_eq!(state.get(&1u64).unwrap().size, Size::new(80, 80));
+
+        assert!(state.resize_floating(&2u64, Direction::North(10), 20));
+        assert_eq!(state.get(&2u64).unwrap().size, Size::new(100, 1));
+    }
+
+    #[test]
+    fn workspace_occupancy_counts_tiled_clients() {
+        let mut state = ClientState::new().with_virtualscreens(3);
+        state.insert(Client::new_default(1));
+        state.insert(Client::new_default(2));
+        state.insert(
+            Client::new_default(3).with_window_type(WindowType::Dialog),
+        );
+
+        state.go_to_nth_virtualscreen(2);
+        state.insert(Client::new_default(4));
+        state.set_urgent(&4u64, true);
+
+        let info =
+            |index, window_count, is_current, has_urgent| WorkspaceInfo {
+                index,
+                window_count,
+                is_current,
+                has_urgent,
+            };
+
+        assert_eq!(
+            state.workspace_occupancy(),
+            vec![
+                info(0, 2, false, false),
+                info(1, 0, false, false),
+                info(2, 1, true, true),
+            ]
+        );
+        assert_eq!(state.iter_workspace(3).count(), 0);
+    }
+
+    #[test]
+    fn tile_weights_share_stack_height() {
+        let mut state = ClientState::new()
+            .with_screen_size((1000, 800).into())
+            .with_gap(0)
+            .with_border(0);
+        for window in 1..=4 {
+            state.insert(Client::new_default(window));
+        }
+
+        assert_eq!(
+            weighted_splits(800, &[1.0; 3]),
+            [(0, 266), (266, 266), (532, 266)]
+        );
+
+        assert!(state.change_weight(&3u64, 1.0));
+        let heights = [2u64, 3, 4]
+            .iter()
+            .map(|k| state.get(k).unwrap().size.height)
+            .collect::<Vec<_>>();
+        assert_eq!(heights, vec![200, 400, 200]);
+
+        // weights are clamped and have no effect on floating clients
+        assert!(state.change_weight(&3u64, 100.0));
+        assert!(!state.change_weight(&3u64, 1.0));
+        state.set_floating(&2u64);
+        assert!(!state.change_weight(&2u64, 1.0));
+
+        state.reset_layout_params();
+        assert_eq!(state.get(&3u64).unwrap().weight, 1.0);
+    }
+
+    #[test]
+    fn attach_policy_insert_order() {
+        let stacks = |policy: AttachPolicy, nmaster: i32| {
+            let mut state = ClientState::new().with_attach_policy(policy);
+            state.change_nmaster(nmaster - 1);
+            for k in 1..=4 {
+                state.insert(Client::new_default(k));
+            }
+
+            let vs = state.virtual_screens.get_current();
+            (vs.master.clone(), vs.aux.clone())
+        };
+
+        // new masters push the last master client to the top of aux
+        assert_eq!(stacks(AttachPolicy::Master, 1), (vec![4], vec![3, 2, 1]));
+        assert_eq!(stacks(AttachPolicy::Master, 2), (vec![4, 3], vec![2, 1]));
+        assert_eq!(stacks(AttachPolicy::Aux, 1), (vec![1], vec![4, 3, 2]));
+        assert_eq!(stacks(AttachPolicy::Bottom, 1), (vec![1], vec![2, 3, 4]));
+        assert_eq!(stacks(AttachPolicy::Bottom, 2), (vec![1, 2], vec![3, 4]));
+    }
+
+    #[test]
+    fn dialogs_are_fitted_into_work_area() {
+        let mut state = ClientState::new()
+            .with_screen_size(Size::new(1000, 800))
+            .with_border(2)
+            .with_reserved(Strut::new(0, 0, 30, 0));
+        let dialog = |key, size| {
+            Client::new_default(key)
+                .with_window_type(WindowType::Dialog)
+                .with_size(size)
+        };
+
+        state.insert(dialog(1, Size::new(300, 200)));
+        let client = state.get(&1u64).unwrap();
+        assert_eq!(client.rect(), rect(0, 30, 300, 200));
+
+        // too large dialogs are shrunk to cover the work area exactly
+        state.insert(dialog(2, Size::new(2000, 2000)));
+        let client = state.get(&2u64).unwrap();
+        assert_eq!(client.rect(), rect(0, 30, 996, 766));
+    }
+
+    #[test]
+    fn switch_order_lists_current_screen_first() {
+        let mut state = ClientState::new().with_virtualscreens(2);
+        state.insert(Client::new_default(1));
+        state.insert(Client::new_default(2));
+        state.go_to_nth_virtualscreen(1);
+        state.insert(Client::new_default(3));
+        state.insert(
+            Client::new_default(4).with_window_type(WindowType::Dialog),
+        );
+        state.insert(Client::new_default(5).with_window_type(WindowType::Dock));
+        state.toggle_tag(&1u64, 1);
+
+        let keys = state
+            .iter_switch_order()
+            .map(|(&k, _)| k)
+            .collect::<Vec<_>>();
+        assert_eq!(keys, vec![3, 1, 4, 2]);
+    }
+
+    #[test]
+    fn master_position_transforms_tiles() {
+        let tiles = |position: MasterPosition| {
+            let mut state = ClientState::new()
+                .with_screen_size(Size::new(1000, 800))
+                .with_master_position(position);
+            for k in 1..=3 {
+                state.insert(Client::new_default(k));
+            }
+
+            (1..=3u64)
+                .map(|k| state.get(&k).unwrap().rect())
+                .collect::<Vec<_>>()
+        };
+
+        let left = tiles(MasterPosition::Left);
+        let right = tiles(MasterPosition::Right);
+        assert_eq!(left[0], rect(0, 0, 500, 800));
+
+        // master on the right mirrors the x coordinates
+        for (l, r) in left.iter().zip(&right) {
+            assert_eq!(r.origin.x, 1000 - l.right());
+            assert_eq!((r.origin.y, r.size), (l.origin.y, l.size));
+        }
+
+        assert_eq!(
+            tiles(MasterPosition::Top),
+            vec![
+                rect(0, 0, 1000, 400),
+                rect(0, 400, 500, 400),
+                rect(500, 400, 500, 400)
+            ]
+        );
+        assert_eq!(tiles(MasterPosition::Bottom)[0], rect(0, 400, 1000, 400));
+    }
+
+    #[test]
+    fn promote_to_master_moves_client_to_master_head() {
+        let mut state = ClientState::new();
+        state.change_nmaster(1);
+        for k in 1..=4 {
+            state.insert(Client::new_default(k));
+        }
+        let stacks = |state: &ClientState| {
+            let vs = state.virtual_screens.get_current();
+            (vs.master.clone(), vs.aux.clone())
+        };
+
+        assert!(state.promote_to_master(&2u64));
+        assert_eq!(stacks(&state), (vec![2, 1], vec![3, 4]));
+
+        assert!(state.promote_to_master(&4u64));
+        assert_eq!(stacks(&state), (vec![4, 2], vec![1, 3]));
+
+        assert!(!state.promote_to_master(&4u64));
+        assert!(!state.promote_to_master(&5u64));
+    }
+}
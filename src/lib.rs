@@ -1,6 +1,7 @@
 pub mod backends;
 pub mod clients;
 pub mod state;
+pub mod ui;
 pub mod util;
 
 pub mod error {
@@ -1,5 +1,3 @@
-use std::io::Read;
-
 use log::{debug, error, info, trace, warn};
 use log4rs::{
     append::{console::ConsoleAppender, file::FileAppender},
@@ -46,18 +44,7 @@ fn main() {
     let mut config_path = std::path::PathBuf::from(env!("HOME"));
     config_path.push(".config/nirgendwm.toml");
 
-    let config = std::fs::File::open(config_path)
-        .and_then(|mut file| {
-            let mut content = String::new();
-            file.read_to_string(&mut content)?;
-            Ok(content)
-        })
-        .and_then(|content| Ok(toml::from_str::<WMConfig>(&content)?))
-        .unwrap_or_else(|e| {
-            warn!("error parsing config file: {}", e);
-            info!("falling back to default config.");
-            WMConfig::default()
-        });
+    let config = WMConfig::load(config_path);
 
     wm::state::WindowManager::<wm::backends::xlib::XLib>::new(config).run();
 }
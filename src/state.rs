@@ -1,25 +1,39 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::RefCell,
+    io::Read,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
 
-use log::{error, info};
+use log::{error, info, warn};
 
 use x11::xlib::{self, Window};
 
-use crate::backends::structs::WindowType;
+use crate::backends::structs::{CursorShape, Strut, WindowType};
 use crate::backends::window_event::{
-    FullscreenEvent, FullscreenState, WindowNameEvent, WindowTypeChangedEvent,
+    DesktopChangeEvent, FullscreenEvent, FullscreenState, MaximizeEvent,
+    MaximizeState, MinimizeEvent, MoveResizeAction, MoveResizeGrabEvent,
+    MoveResizeRequestEvent, PongEvent, ScreenChangeEvent, UrgencyEvent,
+    WindowNameEvent, WindowTypeChangedEvent,
 };
-use crate::util::{Point, Size};
+use crate::util::{self, Point, Rect, Size};
 use crate::{
     backends::{
-        keycodes::{MouseButton, VirtualKeyCode},
+        keycodes::{KeyOrButton, MouseButton, VirtualKeyCode},
         window_event::{
-            ButtonEvent, ConfigureEvent, KeyBind, KeyEvent, KeyState, MapEvent,
-            ModifierKey, ModifierState, MotionEvent, MouseBind, WindowEvent,
+            ButtonEvent, ConfigureEvent, KeyBind, KeyEvent, KeyOrMouseBind,
+            KeyState, MapEvent, ModifierKey, ModifierState, MotionEvent,
+            MouseBind, WindowEvent,
         },
         xlib::XLib,
-        WindowServerBackend,
+        BackendConfig, WindowServerBackend,
+    },
+    clients::{
+        AttachPolicy, Client, ClientEntry, ClientKey, ClientState, LayoutMode,
+        MasterPosition, SavedLayout, WorkspaceInfo, DEFAULT_UNFOCUSABLE_TYPES,
     },
-    clients::{Client, ClientEntry, ClientKey, ClientState},
 };
 
 use serde::Deserialize;
@@ -29,18 +43,132 @@ Contains static config data for the window manager, the sort of stuff you might
 be able to configure in a config file.
  */
 #[derive(Debug, Deserialize)]
+#[serde(default)]
 pub struct WMConfig {
     num_virtualscreens: usize,
     mod_key: ModifierKey,
+    /// modifier used for moving and resizing windows with the mouse,
+    /// defaults to `mod_key`
+    #[serde(default)]
+    mouse_mod_key: Option<ModifierKey>,
+    /// additional modifier every keybind and mouse bind is also grabbed
+    /// with, in place of `mod_key` and `mouse_mod_key`, e.g. for testing in
+    /// a nested X server.
+    #[serde(default)]
+    extra_mod_key: Option<ModifierKey>,
+    /// shorthand for setting both `inner_gap` and `outer_gap`
     gap: Option<i32>,
+    /// gap between adjacent tiled windows, defaults to `gap`
+    inner_gap: Option<i32>,
+    /// gap between tiled windows and the screen edge, defaults to `gap`
+    outer_gap: Option<i32>,
     kill_clients_on_exit: bool,
     #[serde(default = "WMConfig::default_active_window_border_color")]
     active_window_border_color: String,
     #[serde(default = "WMConfig::default_inactive_window_border_color")]
     inactive_window_border_color: String,
+    /// color of a second border drawn along the inside of window borders
+    #[serde(default = "WMConfig::default_inner_border_color")]
+    inner_border_color: String,
+    /// width of the inner border, it is only drawn if it is thinner than the
+    /// border. 0 disables it.
+    #[serde(default)]
+    inner_border_width: i32,
     #[serde(default = "WMConfig::default_terminal")]
     terminal_command: (String, Vec<String>),
     border_width: Option<i32>,
+    /// border width of unfocused windows, defaults to `border_width`
+    #[serde(default)]
+    unfocused_border_width: Option<i32>,
+    /// require the kill keybind to be pressed twice for clients that don't
+    /// support `WM_DELETE_WINDOW` and would be killed forcefully.
+    #[serde(default)]
+    confirm_kill_without_delete: bool,
+    /// distance in pixels floating windows are moved by with the keyboard
+    #[serde(default = "WMConfig::default_nudge_step")]
+    nudge_step: i32,
+    /// distance in pixels floating windows are resized by with the keyboard
+    #[serde(default = "WMConfig::default_resize_step")]
+    resize_step: i32,
+    /// distance in pixels within which dragged floating windows snap to the
+    /// edges of the screen and of other floating windows, 0 disables snapping
+    #[serde(default = "WMConfig::default_snap_distance")]
+    snap_distance: i32,
+    /// keep floating windows within the screen, excluding space reserved
+    /// by struts
+    #[serde(default)]
+    constrain_floating: bool,
+    /// with `constrain_floating`, the part of a floating window in pixels
+    /// that has to stay on the screen, all of it if unset
+    #[serde(default)]
+    constrain_floating_margin: Option<i32>,
+    /// pixels at the top of the screen kept free of tiled windows, for bars
+    /// that don't reserve space with a strut. the margins aren't added to
+    /// struts at the same edge, the larger of the two is kept free.
+    #[serde(default)]
+    reserved_top: i32,
+    /// like `reserved_top`, for the bottom of the screen
+    #[serde(default)]
+    reserved_bottom: i32,
+    /// like `reserved_top`, for the left edge of the screen
+    #[serde(default)]
+    reserved_left: i32,
+    /// like `reserved_top`, for the right edge of the screen
+    #[serde(default)]
+    reserved_right: i32,
+    /// where new windows are placed in the tiling layout
+    #[serde(default)]
+    attach_policy: AttachPolicy,
+    /// where the master stack is placed in the master stack and spiral
+    /// layouts
+    #[serde(default)]
+    master_position: MasterPosition,
+    #[serde(default)]
+    layout: LayoutMode,
+    /// name advertised to clients and pagers as the window manager's name
+    #[serde(default = "WMConfig::default_wm_name")]
+    wm_name: String,
+    /// name of the cursor shown over the root window in the X cursor font,
+    /// e.g. `left_ptr` or `crosshair`
+    #[serde(default = "WMConfig::default_cursor")]
+    cursor: String,
+    /// window types that never receive the focus
+    #[serde(default = "WMConfig::default_unfocusable_types")]
+    unfocusable_types: Vec<WindowType>,
+    /// background color of the root window, left untouched if unset
+    #[serde(default)]
+    root_color: Option<String>,
+    /// environment variables set for spawned programs
+    #[serde(default)]
+    env: Vec<(String, String)>,
+    /// focus new windows when they are mapped, windows that demand attention
+    /// are always focused
+    #[serde(default = "WMConfig::default_focus_new_windows")]
+    focus_new_windows: bool,
+    /// opacity of unfocused windows from 0.0 to 1.0, applied by a compositor
+    #[serde(default)]
+    inactive_opacity: Option<f32>,
+    /// process names of terminals, e.g. `["xterm"]`, that are hidden while a
+    /// window of a program started from them is open, which takes their
+    /// place in the tiling layout
+    #[serde(default)]
+    swallow_terminals: Vec<String>,
+    /// ring the bell when a program fails to start
+    #[serde(default)]
+    bell_on_spawn_failure: bool,
+    /// switch back to the previous virtual screen when the last tiled window
+    /// on the current one closes
+    #[serde(default)]
+    leave_empty_virtualscreen: bool,
+    /// programs started along with the window manager as
+    /// `[virtual screen, command, args]`, their first window is moved to
+    /// the virtual screen, counting from 1
+    #[serde(default)]
+    autostart: Vec<(usize, String, Vec<String>)>,
+    /// file the placement of clients is saved to on exit and restored from
+    /// on startup, `nirgendwm-layout.json` in the cache directory by default
+    #[serde(default = "WMConfig::default_layout_file")]
+    layout_file: Option<PathBuf>,
 }
 
 impl WMConfig {
@@ -52,9 +180,140 @@ impl WMConfig {
         "#444444".to_string()
     }
 
+    fn default_inner_border_color() -> String {
+        "#000000".to_string()
+    }
+
+    fn default_wm_name() -> String {
+        BackendConfig::default().wm_name
+    }
+
+    fn default_cursor() -> String {
+        BackendConfig::default().cursor
+    }
+
+    fn default_unfocusable_types() -> Vec<WindowType> {
+        DEFAULT_UNFOCUSABLE_TYPES.to_vec()
+    }
+
     fn default_terminal() -> (String, Vec<String>) {
         ("xterm".to_string(), vec![])
     }
+
+    fn default_layout_file() -> Option<PathBuf> {
+        dirs::cache_dir().map(|dir| dir.join("nirgendwm-layout.json"))
+    }
+
+    fn default_nudge_step() -> i32 {
+        20
+    }
+
+    fn default_resize_step() -> i32 {
+        20
+    }
+
+    fn default_snap_distance() -> i32 {
+        15
+    }
+
+    fn default_focus_new_windows() -> bool {
+        true
+    }
+
+    fn inner_gap(&self) -> i32 {
+        self.inner_gap.or(self.gap).unwrap_or(1)
+    }
+
+    fn outer_gap(&self) -> i32 {
+        self.outer_gap.or(self.gap).unwrap_or(1)
+    }
+
+    fn mouse_mod_key(&self) -> ModifierKey {
+        self.mouse_mod_key.unwrap_or(self.mod_key)
+    }
+
+    /// returns `modifiers` with `primary` replaced by `extra_mod_key`, if
+    /// `extra_mod_key` is set and `modifiers` contains `primary`.
+    fn extra_modifiers(
+        &self,
+        modifiers: ModifierState,
+        primary: ModifierKey,
+    ) -> Option<ModifierState> {
+        self.extra_mod_key
+            .filter(|_| modifiers.contains(ModifierState::from([primary])))
+            .map(|extra| {
+                let mut modifiers = modifiers;
+                modifiers.unset_mod(primary);
+                modifiers.with_mod(extra)
+            })
+    }
+
+    /// loads the config from `path`, falling back to the default config if
+    /// the file doesn't exist or can't be read.
+    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref();
+
+        match std::fs::read_to_string(path) {
+            Ok(content) => Self::from_toml_str(&content),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                info!("no config file at {:?}, using default config.", path);
+                Self::default()
+            }
+            Err(err) => {
+                warn!("failed to read config file {:?}: {}", path, err);
+                info!("falling back to default config.");
+                Self::default()
+            }
+        }
+    }
+
+    /// parses the config from a toml string. if parsing fails the error is
+    /// logged and every key that fails to parse on its own is replaced by
+    /// its default value, keeping the rest of the config intact.
+    pub fn from_toml_str(content: &str) -> Self {
+        match toml::from_str::<Self>(content) {
+            Ok(config) => config,
+            Err(err) => {
+                // toml includes the line and column in the error message
+                error!("error parsing config file: {}", err);
+
+                Self::recover(content)
+            }
+        }
+    }
+
+    fn recover(content: &str) -> Self {
+        let table = match toml::from_str::<toml::value::Table>(content) {
+            Ok(table) => table,
+            Err(_) => {
+                info!("falling back to default config.");
+                return Self::default();
+            }
+        };
+
+        let valid = table
+            .into_iter()
+            .filter(|(key, value)| {
+                let mut single = toml::value::Table::new();
+                single.insert(key.clone(), value.clone());
+
+                match toml::Value::Table(single).try_into::<Self>() {
+                    Ok(_) => true,
+                    Err(err) => {
+                        warn!(
+                            "invalid value for `{}`: {}, using default.",
+                            key, err
+                        );
+                        false
+                    }
+                }
+            })
+            .collect::<toml::value::Table>();
+
+        toml::Value::Table(valid)
+            .try_into::<Self>()
+            .unwrap_or_else(|_| Self::default())
+    }
 }
 
 impl Default for WMConfig {
@@ -62,14 +321,46 @@ impl Default for WMConfig {
         Self {
             num_virtualscreens: 10,
             mod_key: ModifierKey::Super,
+            mouse_mod_key: None,
+            extra_mod_key: None,
             gap: Some(2),
+            inner_gap: None,
+            outer_gap: None,
             kill_clients_on_exit: false,
             active_window_border_color:
                 Self::default_active_window_border_color(),
             inactive_window_border_color:
                 Self::default_inactive_window_border_color(),
+            inner_border_color: Self::default_inner_border_color(),
+            inner_border_width: 0,
             terminal_command: Self::default_terminal(),
             border_width: Some(1),
+            unfocused_border_width: None,
+            confirm_kill_without_delete: false,
+            nudge_step: Self::default_nudge_step(),
+            resize_step: Self::default_resize_step(),
+            snap_distance: Self::default_snap_distance(),
+            constrain_floating: false,
+            constrain_floating_margin: None,
+            reserved_top: 0,
+            reserved_bottom: 0,
+            reserved_left: 0,
+            reserved_right: 0,
+            attach_policy: AttachPolicy::default(),
+            master_position: MasterPosition::default(),
+            layout: LayoutMode::default(),
+            wm_name: Self::default_wm_name(),
+            cursor: Self::default_cursor(),
+            unfocusable_types: Self::default_unfocusable_types(),
+            root_color: None,
+            env: Vec::new(),
+            focus_new_windows: Self::default_focus_new_windows(),
+            inactive_opacity: None,
+            swallow_terminals: Vec::new(),
+            bell_on_spawn_failure: false,
+            leave_empty_virtualscreen: false,
+            autostart: Vec::new(),
+            layout_file: Self::default_layout_file(),
         }
     }
 }
@@ -82,10 +373,43 @@ where
     move_resize_window: MoveResizeInfo,
     keybinds: Rc<RefCell<Vec<KeyBinding<B>>>>,
     backend: B,
+    /// window that is waiting for a second kill keypress to be killed forcefully
+    pending_kill: Option<(Window, Instant)>,
+    /// windows asked to close that haven't answered a `_NET_WM_PING` yet and
+    /// when they were pinged
+    pending_pings: Vec<(Window, Instant)>,
+    /// clients listed in the open window switcher and the selected entry
+    switcher: Option<(Vec<Window>, usize)>,
+    /// window stashed with `stash_to_scratchpad`, shown and hidden with
+    /// `toggle_scratchpad`
+    scratchpad: Option<Window>,
+    hooks: Vec<WmEventHook>,
+    /// spawned programs that haven't been reaped yet
+    children: RefCell<Vec<std::process::Child>>,
+    /// callbacks run once their deadline has passed, see `add_timer`
+    timers: Vec<(Instant, Timer<B>)>,
+    /// virtual screens the next windows of programs started with `spawn_on`
+    /// are moved to, see `take_pending_spawn`
+    pending_spawns: Vec<PendingSpawn>,
+    /// work area last published with `set_work_areas`
+    work_area: Option<Rect>,
 
     config: WMConfig,
 }
 
+/// High level window manager events passed to hooks registered with
+/// `WindowManager::on_event`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WmEvent {
+    ClientAdded(Window),
+    ClientRemoved(Window),
+    FocusChanged(Window),
+    /// index of the virtual screen that is now current
+    WorkspaceChanged(usize),
+}
+
+pub type WmEventHook = Box<dyn FnMut(&WmEvent)>;
+
 #[derive(Debug, Clone, Copy)]
 pub enum Direction {
     West(usize),
@@ -94,6 +418,15 @@ pub enum Direction {
     South(usize),
 }
 
+/// virtual screen a window of a program started with `spawn_on` should be
+/// moved to.
+#[derive(Debug, Clone, Copy)]
+struct PendingSpawn {
+    pid: Option<u32>,
+    virtualscreen: usize,
+    deadline: Instant,
+}
+
 enum MoveResizeInfo {
     Move(MoveInfoInner),
     Resize(ResizeInfoInner),
@@ -105,52 +438,160 @@ struct MoveInfoInner {
     window: Window,
     starting_cursor_pos: Point<i32>,
     starting_window_pos: Point<i32>,
+    /// the window was tiled before the move floated it, only then dropping
+    /// it onto a tiled window tiles it again
+    from_tiled: bool,
 }
 
 #[derive(Debug)]
 struct ResizeInfoInner {
     window: Window,
     starting_cursor_pos: Point<i32>,
+    starting_window_pos: Point<i32>,
     starting_window_size: Size<i32>,
+    corner: Corner,
+    /// width to height ratio kept while resizing, if the aspect is locked
+    aspect: Option<f32>,
+}
+
+/// corner of a window that is dragged while resizing it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    /// returns the corner of the window covering `rect` nearest to `cursor`,
+    /// or `BottomRight` if `cursor` is outside of the window.
+    fn nearest(rect: Rect, cursor: Point<i32>) -> Self {
+        if !rect.contains(cursor) {
+            return Self::BottomRight;
+        }
+
+        let center = rect.center();
+        let left = cursor.x < center.x;
+        let top = cursor.y < center.y;
+
+        match (left, top) {
+            (true, true) => Self::TopLeft,
+            (false, true) => Self::TopRight,
+            (true, false) => Self::BottomLeft,
+            (false, false) => Self::BottomRight,
+        }
+    }
+
+    fn is_left(&self) -> bool {
+        matches!(self, Self::TopLeft | Self::BottomLeft)
+    }
+
+    fn is_top(&self) -> bool {
+        matches!(self, Self::TopLeft | Self::TopRight)
+    }
+
+    /// returns the position of this corner of the window covering `rect`
+    fn position(&self, rect: Rect) -> Point<i32> {
+        Point::new(
+            if self.is_left() {
+                rect.origin.x
+            } else {
+                rect.right()
+            },
+            if self.is_top() {
+                rect.origin.y
+            } else {
+                rect.bottom()
+            },
+        )
+    }
 }
 
+type Timer<B> = Box<dyn FnOnce(&mut WindowManager<B>)>;
+
 use derivative::*;
 
 #[derive(Derivative)]
 #[derivative(Clone(bound = ""))]
+/// an action bound to a key or a mouse button, along with modifiers.
 struct KeyBinding<B: WindowServerBackend> {
-    key: KeyBind,
-    closure: Rc<dyn Fn(&mut WindowManager<B>, &KeyEvent<B::Window>)>,
+    key: KeyOrMouseBind,
+    closure: Rc<dyn Fn(&mut WindowManager<B>, &KeyOrMouseBind)>,
 }
 
 impl<B: WindowServerBackend> KeyBinding<B> {
-    pub fn new<F>(key: KeyBind, cb: F) -> Self
+    pub fn new<K, F>(key: K, cb: F) -> Self
     where
-        F: Fn(&mut WindowManager<B>, &KeyEvent<B::Window>),
+        K: Into<KeyOrMouseBind>,
+        F: Fn(&mut WindowManager<B>, &KeyOrMouseBind),
         F: 'static,
     {
         Self {
-            key,
+            key: key.into(),
             closure: Rc::new(cb),
         }
     }
 
-    pub fn call(&self, wm: &mut WindowManager<B>, ev: &KeyEvent<B::Window>) {
-        (self.closure)(wm, ev);
+    pub fn call(&self, wm: &mut WindowManager<B>, bind: &KeyOrMouseBind) {
+        (self.closure)(wm, bind);
     }
 }
 
+/// removes all bindings for `key` from `keybinds`, returns `true` if any
+/// binding was removed.
+fn remove_keybindings<B: WindowServerBackend>(
+    keybinds: &mut Vec<KeyBinding<B>>,
+    key: &KeyOrMouseBind,
+) -> bool {
+    let len = keybinds.len();
+    keybinds.retain(|kb| kb.key != *key);
+
+    len != keybinds.len()
+}
+
+/// set by the `SIGTERM` handler, the event loop quits cleanly once it sees
+/// it.
+static TERMINATION_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn request_termination(_: libc::c_int) {
+    TERMINATION_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// `SIGCHLD` handler, the signal interrupts waiting for events so exited
+/// children are reaped right away.
+extern "C" fn child_exited(_: libc::c_int) {}
+
 impl<B> WindowManager<B>
 where
     B: WindowServerBackend<Window = xlib::Window>,
 {
     pub fn new(config: WMConfig) -> Self {
-        let backend = B::build();
+        let backend = B::build_with(
+            BackendConfig::default()
+                .with_wm_name(config.wm_name.clone())
+                .with_cursor(config.cursor.clone()),
+        );
 
         let clients = ClientState::new()
             .with_virtualscreens(config.num_virtualscreens)
-            .with_gap(config.gap.unwrap_or(1))
+            .with_gaps(config.inner_gap(), config.outer_gap())
             .with_border(config.border_width.unwrap_or(1))
+            .with_unfocused_border(config.unfocused_border_width)
+            .with_constrain_floating(
+                config.constrain_floating,
+                config.constrain_floating_margin,
+            )
+            .with_reserved(Strut::new(
+                config.reserved_left,
+                config.reserved_right,
+                config.reserved_top,
+                config.reserved_bottom,
+            ))
+            .with_attach_policy(config.attach_policy)
+            .with_master_position(config.master_position)
+            .with_unfocusable_types(config.unfocusable_types.clone())
+            .with_layout(config.layout)
             .with_screen_size(backend.screen_size());
 
         Self {
@@ -158,32 +599,43 @@ where
             move_resize_window: MoveResizeInfo::None,
             keybinds: Rc::new(RefCell::new(Vec::new())),
             backend,
+            pending_kill: None,
+            pending_pings: Vec::new(),
+            switcher: None,
+            scratchpad: None,
+            hooks: Vec::new(),
+            children: RefCell::new(Vec::new()),
+            timers: Vec::new(),
+            pending_spawns: Vec::new(),
+            work_area: None,
             config,
         }
         .init()
     }
 
     fn init(mut self) -> Self {
-        self.backend.add_keybind(
+        self.add_mouse_bind(
             MouseBind::new(MouseButton::Left)
-                .with_mod(self.config.mod_key)
-                .into(),
+                .with_mod(self.config.mouse_mod_key()),
         );
-        self.backend.add_keybind(
+        self.add_mouse_bind(
             MouseBind::new(MouseButton::Middle)
-                .with_mod(self.config.mod_key)
-                .into(),
+                .with_mod(self.config.mouse_mod_key()),
         );
-        self.backend.add_keybind(
+        self.add_mouse_bind(
             MouseBind::new(MouseButton::Right)
-                .with_mod(self.config.mod_key)
-                .into(),
+                .with_mod(self.config.mouse_mod_key()),
+        );
+        self.add_mouse_bind(
+            MouseBind::new(MouseButton::Right)
+                .with_mod(self.config.mouse_mod_key())
+                .with_mod(ModifierKey::Shift),
         );
 
         self.add_keybind(KeyBinding::new(
             KeyBind::new(VirtualKeyCode::P).with_mod(self.config.mod_key),
             |wm, _| {
-                wm.spawn(
+                let _ = wm.spawn(
                     &"dmenu_run",
                     &[
                         "-m",
@@ -199,7 +651,7 @@ where
                         "-sf",
                         "#eeeeee",
                     ],
-                )
+                );
             },
         ));
 
@@ -218,6 +670,13 @@ where
             |wm, _| wm.handle_switch_stack(),
         ));
 
+        self.add_keybind(KeyBinding::new(
+            KeyBind::new(VirtualKeyCode::M)
+                .with_mod(self.config.mod_key)
+                .with_mod(ModifierKey::Shift),
+            |wm, _| wm.handle_promote_to_master(),
+        ));
+
         self.add_keybind(KeyBinding::new(
             KeyBind::new(VirtualKeyCode::F).with_mod(self.config.mod_key),
             |wm, _| {
@@ -231,6 +690,34 @@ where
             },
         ));
 
+        // float all tiled windows on the current virtual screen and tile
+        // them again
+
+        self.add_keybind(KeyBinding::new(
+            KeyBind::new(VirtualKeyCode::F)
+                .with_mod(self.config.mod_key)
+                .with_mod(ModifierKey::Shift),
+            |wm, _| {
+                wm.clients.toggle_all_floating();
+                wm.arrange_clients();
+            },
+        ));
+
+        // let the focused tiled window cover the whole tiling area and
+        // restore the split again
+
+        self.add_keybind(KeyBinding::new(
+            KeyBind::new(VirtualKeyCode::Z).with_mod(self.config.mod_key),
+            |wm, _| {
+                if let Some(key) =
+                    wm.clients.get_focused().into_option().map(|c| c.key())
+                {
+                    wm.clients.toggle_zoom(&key);
+                    wm.arrange_clients();
+                }
+            },
+        ));
+
         self.add_keybind(KeyBinding::new(
             KeyBind::new(VirtualKeyCode::Q).with_mod(self.config.mod_key),
             |wm, _| wm.kill_client(),
@@ -248,10 +735,22 @@ where
                 .with_mod(self.config.mod_key)
                 .with_mod(ModifierKey::Shift),
             |wm, _| {
-                wm.spawn(
+                let _ = wm.spawn(
                     &wm.config.terminal_command.0,
                     &wm.config.terminal_command.1,
-                )
+                );
+            },
+        ));
+
+        // always available in case `terminal_command` is broken
+
+        self.add_keybind(KeyBinding::new(
+            KeyBind::new(VirtualKeyCode::Return)
+                .with_mod(self.config.mod_key)
+                .with_mod(ModifierKey::Control)
+                .with_mod(ModifierKey::Shift),
+            |wm, _| {
+                let _ = wm.spawn_first_available(Self::FALLBACK_TERMINALS);
             },
         ));
 
@@ -275,15 +774,17 @@ where
             |wm, _| wm.move_focus(Direction::east()),
         ));
 
-        // resize master stack
+        // resize master stack, or move the focused window if it is floating
 
         self.add_keybind(KeyBinding::new(
             KeyBind::new(VirtualKeyCode::K)
                 .with_mod(self.config.mod_key)
                 .with_mod(ModifierKey::Shift),
             |wm, _| {
-                wm.clients.change_master_size(0.1);
-                wm.arrange_clients();
+                if !wm.nudge_focused(Direction::north()) {
+                    wm.clients.change_master_size(0.1);
+                    wm.arrange_clients();
+                }
             },
         ));
 
@@ -292,304 +793,1278 @@ where
                 .with_mod(self.config.mod_key)
                 .with_mod(ModifierKey::Shift),
             |wm, _| {
-                wm.clients.change_master_size(-0.1);
-                wm.arrange_clients();
+                if !wm.nudge_focused(Direction::south()) {
+                    wm.clients.change_master_size(-0.1);
+                    wm.arrange_clients();
+                }
             },
         ));
 
-        self.add_vs_switch_keybinds();
-
-        self.backend.set_active_window_border_color(
-            &self.config.active_window_border_color,
-        );
-        self.backend.set_inactive_window_border_color(
-            &self.config.inactive_window_border_color,
-        );
-
-        self
-    }
-
-    fn add_keybind(&mut self, keybind: KeyBinding<B>) {
-        self.backend.add_keybind((&keybind.key).into());
-        self.keybinds.borrow_mut().push(keybind);
-    }
+        // resize the master stack in layouts that split the screen vertically,
+        // or resize the focused window if it is floating
 
-    fn add_vs_switch_keybinds(&mut self) {
-        // Old keybinds
+        self.add_keybind(KeyBinding::new(
+            KeyBind::new(VirtualKeyCode::K)
+                .with_mod(self.config.mod_key)
+                .with_mod(ModifierKey::Control),
+            |wm, _| {
+                if !wm.resize_focused(Direction::north()) {
+                    wm.clients.change_master_height(0.1);
+                    wm.arrange_clients();
+                }
+            },
+        ));
 
         self.add_keybind(KeyBinding::new(
-            KeyBind::new(VirtualKeyCode::Left).with_mod(self.config.mod_key),
-            |wm, _| wm.rotate_virtual_screen(Direction::West(1)),
+            KeyBind::new(VirtualKeyCode::J)
+                .with_mod(self.config.mod_key)
+                .with_mod(ModifierKey::Control),
+            |wm, _| {
+                if !wm.resize_focused(Direction::south()) {
+                    wm.clients.change_master_height(-0.1);
+                    wm.arrange_clients();
+                }
+            },
         ));
 
         self.add_keybind(KeyBinding::new(
             KeyBind::new(VirtualKeyCode::H)
                 .with_mod(self.config.mod_key)
-                .with_mod(ModifierKey::Shift),
-            |wm, _| wm.rotate_virtual_screen(Direction::West(1)),
+                .with_mod(ModifierKey::Control),
+            |wm, _| {
+                wm.resize_focused(Direction::west());
+            },
         ));
 
         self.add_keybind(KeyBinding::new(
-            KeyBind::new(VirtualKeyCode::Right).with_mod(self.config.mod_key),
-            |wm, _| wm.rotate_virtual_screen(Direction::East(1)),
+            KeyBind::new(VirtualKeyCode::L)
+                .with_mod(self.config.mod_key)
+                .with_mod(ModifierKey::Control),
+            |wm, _| {
+                wm.resize_focused(Direction::east());
+            },
         ));
 
+        // grow or shrink the focused tiled window within its stack
+
         self.add_keybind(KeyBinding::new(
-            KeyBind::new(VirtualKeyCode::L)
+            KeyBind::new(VirtualKeyCode::K)
                 .with_mod(self.config.mod_key)
+                .with_mod(ModifierKey::Control)
                 .with_mod(ModifierKey::Shift),
-            |wm, _| wm.rotate_virtual_screen(Direction::East(1)),
+            |wm, _| wm.change_focused_weight(0.25),
         ));
 
         self.add_keybind(KeyBinding::new(
-            KeyBind::new(VirtualKeyCode::Tab).with_mod(self.config.mod_key),
-            |wm, _| wm.rotate_virtual_screen_back(),
+            KeyBind::new(VirtualKeyCode::J)
+                .with_mod(self.config.mod_key)
+                .with_mod(ModifierKey::Control)
+                .with_mod(ModifierKey::Shift),
+            |wm, _| wm.change_focused_weight(-0.25),
         ));
 
-        // Mod + Num
+        // reset master size, gaps and layout of the current virtual screen
 
-        // Press Mod + `1` to move go to the `1`th virtual screen
         self.add_keybind(KeyBinding::new(
-            KeyBind::new(VirtualKeyCode::One).with_mod(self.config.mod_key),
-            |wm, _| wm.go_to_nth_virtual_screen(1),
+            KeyBind::new(VirtualKeyCode::Equals).with_mod(self.config.mod_key),
+            |wm, _| {
+                wm.clients.reset_layout_params();
+                wm.export_layout();
+                wm.arrange_clients();
+            },
         ));
 
-        // Press Mod + `2` to move go to the `2`th virtual screen
+        // grow or shrink the border of all windows
+
         self.add_keybind(KeyBinding::new(
-            KeyBind::new(VirtualKeyCode::Two).with_mod(self.config.mod_key),
-            |wm, _| wm.go_to_nth_virtual_screen(2),
+            KeyBind::new(VirtualKeyCode::RBracket)
+                .with_mod(self.config.mod_key),
+            |wm, _| wm.change_border(1),
         ));
 
-        // Press Mod + `3` to move go to the `3`th virtual screen
         self.add_keybind(KeyBinding::new(
-            KeyBind::new(VirtualKeyCode::Three).with_mod(self.config.mod_key),
-            |wm, _| wm.go_to_nth_virtual_screen(3),
+            KeyBind::new(VirtualKeyCode::LBracket)
+                .with_mod(self.config.mod_key),
+            |wm, _| wm.change_border(-1),
         ));
 
-        // Press Mod + `4` to move go to the `4`th virtual screen
+        // cycle the layout of the current virtual screen
+
         self.add_keybind(KeyBinding::new(
-            KeyBind::new(VirtualKeyCode::Four).with_mod(self.config.mod_key),
-            |wm, _| wm.go_to_nth_virtual_screen(4),
+            KeyBind::new(VirtualKeyCode::Space).with_mod(self.config.mod_key),
+            |wm, _| wm.cycle_layout(),
         ));
 
-        // Press Mod + `5` to move go to the `5`th virtual screen
+        // pin the focused window above all other windows while it is floating
+
         self.add_keybind(KeyBinding::new(
-            KeyBind::new(VirtualKeyCode::Five).with_mod(self.config.mod_key),
-            |wm, _| wm.go_to_nth_virtual_screen(5),
+            KeyBind::new(VirtualKeyCode::T).with_mod(self.config.mod_key),
+            |wm, _| {
+                if let Some(key) =
+                    wm.clients.get_focused().into_option().map(|c| c.key())
+                {
+                    wm.clients.toggle_pin(&key);
+                    wm.raise_floating_clients();
+                }
+            },
         ));
 
-        // Press Mod + `6` to move go to the `6`th virtual screen
+        // minimize the focused window and restore the last minimized window
+
         self.add_keybind(KeyBinding::new(
-            KeyBind::new(VirtualKeyCode::Six).with_mod(self.config.mod_key),
-            |wm, _| wm.go_to_nth_virtual_screen(6),
+            KeyBind::new(VirtualKeyCode::N).with_mod(self.config.mod_key),
+            |wm, _| {
+                if let Some(key) =
+                    wm.clients.get_focused().into_option().map(|c| c.key())
+                {
+                    wm.toggle_minimize(&key);
+                }
+            },
         ));
 
-        // Press Mod + `7` to move go to the `7`th virtual screen
         self.add_keybind(KeyBinding::new(
-            KeyBind::new(VirtualKeyCode::Seven).with_mod(self.config.mod_key),
-            |wm, _| wm.go_to_nth_virtual_screen(7),
+            KeyBind::new(VirtualKeyCode::N)
+                .with_mod(self.config.mod_key)
+                .with_mod(ModifierKey::Shift),
+            |wm, _| {
+                if let Some(key) = wm.clients.last_minimized() {
+                    wm.toggle_minimize(&key);
+                }
+            },
         ));
 
-        // Press Mod + `8` to move go to the `8`th virtual screen
+        // stash the focused window in the scratchpad and show or hide it
+
         self.add_keybind(KeyBinding::new(
-            KeyBind::new(VirtualKeyCode::Eight).with_mod(self.config.mod_key),
-            |wm, _| wm.go_to_nth_virtual_screen(8),
+            KeyBind::new(VirtualKeyCode::S)
+                .with_mod(self.config.mod_key)
+                .with_mod(ModifierKey::Shift),
+            |wm, _| wm.stash_to_scratchpad(),
         ));
 
-        // Press Mod + `9` to move go to the `9`th virtual screen
         self.add_keybind(KeyBinding::new(
-            KeyBind::new(VirtualKeyCode::Nine).with_mod(self.config.mod_key),
-            |wm, _| wm.go_to_nth_virtual_screen(9),
+            KeyBind::new(VirtualKeyCode::S).with_mod(self.config.mod_key),
+            |wm, _| wm.toggle_scratchpad(),
         ));
 
-        // Press Mod + `0` to move go to the `0`th virtual screen
+        // list all windows and focus one of them
+
         self.add_keybind(KeyBinding::new(
-            KeyBind::new(VirtualKeyCode::Zero).with_mod(self.config.mod_key),
-            |wm, _| wm.go_to_nth_virtual_screen(10),
+            KeyBind::new(VirtualKeyCode::W).with_mod(self.config.mod_key),
+            |wm, _| wm.open_switcher(),
         ));
-    }
 
-    #[allow(unused_mut)]
-    pub fn run(mut self) -> ! {
-        loop {
-            let event = self.backend.next_event();
+        // jump to the most recently urgent window, repeated presses cycle
+        // through all urgent windows
 
-            match event {
-                WindowEvent::KeyEvent(event) => {
-                    if event.state == KeyState::Pressed {
-                        self.handle_keybinds(&event);
-                    }
-                }
-                WindowEvent::ButtonEvent(event) => {
-                    self.button_event(&event);
-                }
-                WindowEvent::MapRequestEvent(MapEvent { window }) => {
-                    if !self.clients.contains(&window) {
-                        self.new_client(window);
-                    }
-                }
-                WindowEvent::UnmapEvent(event) => {
-                    self.clients.remove(&event.window);
-                    self.arrange_clients();
-                }
-                WindowEvent::EnterEvent(event) => {
-                    self.focus_client(&event.window, false);
-                }
-                WindowEvent::MotionEvent(event) => {
-                    self.do_move_resize_window(&event);
-                }
-                WindowEvent::ConfigureEvent(ConfigureEvent {
-                    window,
-                    size,
-                    position,
-                    ..
-                }) => match self.clients.get(&window) {
-                    ClientEntry::Tiled(client)
-                    | ClientEntry::Floating(client) => {
-                        self.backend.configure_window(
-                            window,
-                            Some(client.size),
-                            Some(client.position),
-                            None,
-                        )
-                    }
-                    ClientEntry::Vacant => self.backend.configure_window(
-                        window,
-                        Some(size),
-                        Some(position),
-                        None,
-                    ),
-                },
-                WindowEvent::FullscreenEvent(FullscreenEvent {
+        self.add_keybind(KeyBinding::new(
+            KeyBind::new(VirtualKeyCode::U).with_mod(self.config.mod_key),
+            |wm, _| wm.focus_urgent(),
+        ));
+
+        self.add_keybind(KeyBinding::new(
+            KeyBind::new(VirtualKeyCode::B).with_mod(self.config.mod_key),
+            |wm, _| {
+                wm.clients.toggle_struts();
+                wm.arrange_clients();
+            },
+        ));
+
+        // change number of clients in the master stack
+
+        self.add_keybind(KeyBinding::new(
+            KeyBind::new(VirtualKeyCode::I).with_mod(self.config.mod_key),
+            |wm, _| {
+                wm.clients.change_nmaster(1);
+                wm.arrange_clients();
+            },
+        ));
+
+        self.add_keybind(KeyBinding::new(
+            KeyBind::new(VirtualKeyCode::D).with_mod(self.config.mod_key),
+            |wm, _| {
+                wm.clients.change_nmaster(-1);
+                wm.arrange_clients();
+            },
+        ));
+
+        self.add_vs_switch_keybinds();
+
+        self.backend.set_active_window_border_color(
+            &self.config.active_window_border_color,
+        );
+        self.backend.set_inactive_window_border_color(
+            &self.config.inactive_window_border_color,
+        );
+        self.backend.set_inner_border(
+            &self.config.inner_border_color,
+            self.config.inner_border_width,
+        );
+
+        if let Some(color) = &self.config.root_color {
+            self.backend.set_root_color(color);
+        }
+
+        self.adopt_existing_windows();
+        self.restore_layout();
+        self.export_layout();
+        self.export_work_area();
+
+        self
+    }
+
+    /// manages windows that were already mapped before the window manager started.
+    fn adopt_existing_windows(&mut self) {
+        self.with_server_grabbed(|wm| {
+            if let Some(windows) = wm.backend.all_windows() {
+                for window in windows {
+                    if !wm.clients.contains(&window) {
+                        wm.backend.handle_event(WindowEvent::MapRequestEvent(
+                            MapEvent { window },
+                        ));
+                        wm.new_client(window);
+                    }
+                }
+            }
+        });
+    }
+
+    /// runs `f` with the server grabbed so that other clients don't observe
+    /// intermediate states. the server is ungrabbed however `f` returns.
+    fn with_server_grabbed<T, F>(&mut self, f: F) -> T
+    where
+        F: FnOnce(&mut Self) -> T,
+    {
+        self.backend.grab_server();
+        let result = f(self);
+        self.backend.ungrab_server();
+
+        // requests are queued while the server is grabbed, send them all at
+        // once so no change is left waiting for the next event
+        self.backend.flush();
+
+        result
+    }
+
+    /// writes the placement of all clients to the layout file so it can be
+    /// restored by the next instance of the window manager.
+    fn save_layout(&self) {
+        if let Some(path) = &self.config.layout_file {
+            match serde_json::to_string(&self.clients.serialize_layout())
+                .map_err(|err| err.to_string())
+                .and_then(|layout| {
+                    std::fs::write(path, layout).map_err(|err| err.to_string())
+                }) {
+                Ok(_) => info!("saved layout to {:?}", path),
+                Err(err) => warn!("failed to save layout: {}", err),
+            }
+        }
+    }
+
+    /// restores the placement of clients saved by a previous instance of the
+    /// window manager and removes the layout file.
+    fn restore_layout(&mut self) {
+        let path = match &self.config.layout_file {
+            Some(path) if path.exists() => path.clone(),
+            _ => return,
+        };
+
+        let layout = std::fs::File::open(&path)
+            .and_then(|mut file| {
+                let mut content = String::new();
+                file.read_to_string(&mut content)?;
+                Ok(content)
+            })
+            .map_err(|err| err.to_string())
+            .and_then(|content| {
+                serde_json::from_str::<SavedLayout>(&content)
+                    .map_err(|err| err.to_string())
+            });
+
+        match layout {
+            Ok(layout) => {
+                info!("restoring layout from {:?}", path);
+                self.clients.restore_layout(&layout);
+                self.arrange_clients();
+            }
+            Err(err) => warn!("failed to restore layout: {}", err),
+        }
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    /// registers a hook that is called for every `WmEvent`.
+    pub fn on_event(&mut self, hook: WmEventHook) {
+        self.hooks.push(hook);
+    }
+
+    /// returns the occupancy of every virtual screen, e.g. for hooks that
+    /// update a status bar on `WmEvent::WorkspaceChanged`.
+    pub fn workspace_occupancy(&self) -> Vec<WorkspaceInfo> {
+        self.clients.workspace_occupancy()
+    }
+
+    fn emit(&mut self, event: WmEvent) {
+        for hook in self.hooks.iter_mut() {
+            hook(&event);
+        }
+    }
+
+    /// returns the twin of `key` bound with the extra modifier key, if any.
+    fn extra_bind(&self, key: &KeyOrMouseBind) -> Option<KeyOrMouseBind> {
+        let mod_key = match key.key {
+            KeyOrButton::Key(_) => self.config.mod_key,
+            KeyOrButton::Button(_) => self.config.mouse_mod_key(),
+        };
+
+        self.config
+            .extra_modifiers(key.modifiers, mod_key)
+            .map(|modifiers| KeyOrMouseBind {
+                key: key.key,
+                modifiers,
+            })
+    }
+
+    fn add_keybind(&mut self, keybind: KeyBinding<B>) {
+        if let Some(key) = self.extra_bind(&keybind.key) {
+            let extra = KeyBinding {
+                key,
+                closure: keybind.closure.clone(),
+            };
+
+            self.backend.add_keybind(extra.key.clone());
+            self.keybinds.borrow_mut().push(extra);
+        }
+
+        self.backend.add_keybind(keybind.key.clone());
+        self.keybinds.borrow_mut().push(keybind);
+    }
+
+    /// removes the binding for `key`, a key or a mouse button, along with
+    /// its twin for the extra modifier key and releases their grabs.
+    pub fn unbind<K>(&mut self, key: K)
+    where
+        K: Into<KeyOrMouseBind>,
+    {
+        let key = key.into();
+        let extra = self.extra_bind(&key);
+
+        for key in std::iter::once(key).chain(extra) {
+            if remove_keybindings(&mut self.keybinds.borrow_mut(), &key) {
+                self.backend.remove_keybind(&key);
+            }
+        }
+    }
+
+    fn add_mouse_bind(&mut self, mousebind: MouseBind) {
+        if let Some(modifiers) = self
+            .config
+            .extra_modifiers(mousebind.modifiers, self.config.mouse_mod_key())
+        {
+            self.backend.add_keybind(
+                MouseBind {
+                    button: mousebind.button,
+                    modifiers,
+                }
+                .into(),
+            );
+        }
+
+        self.backend.add_keybind(mousebind.into());
+    }
+
+    /// returns `true` if `modifiers` are the modifiers used for mouse binds.
+    fn is_mouse_mod(&self, modifiers: &ModifierState) -> bool {
+        let mouse_mod = ModifierState::from([self.config.mouse_mod_key()]);
+
+        mouse_mod.eq(modifiers)
+            || self
+                .config
+                .extra_modifiers(mouse_mod, self.config.mouse_mod_key())
+                .map(|extra| extra.eq(modifiers))
+                .unwrap_or(false)
+    }
+
+    fn add_vs_switch_keybinds(&mut self) {
+        // Old keybinds
+
+        self.add_keybind(KeyBinding::new(
+            KeyBind::new(VirtualKeyCode::Left).with_mod(self.config.mod_key),
+            |wm, _| wm.rotate_virtual_screen(Direction::West(1)),
+        ));
+
+        // rotate virtual screens, or move the focused window if it is floating
+
+        self.add_keybind(KeyBinding::new(
+            KeyBind::new(VirtualKeyCode::H)
+                .with_mod(self.config.mod_key)
+                .with_mod(ModifierKey::Shift),
+            |wm, _| {
+                if !wm.nudge_focused(Direction::west()) {
+                    wm.rotate_virtual_screen(Direction::West(1))
+                }
+            },
+        ));
+
+        self.add_keybind(KeyBinding::new(
+            KeyBind::new(VirtualKeyCode::Right).with_mod(self.config.mod_key),
+            |wm, _| wm.rotate_virtual_screen(Direction::East(1)),
+        ));
+
+        // scrolling over a window with the mouse modifier held
+
+        self.add_keybind(KeyBinding::new(
+            MouseBind::new(MouseButton::ScrollUp)
+                .with_mod(self.config.mouse_mod_key()),
+            |wm, _| wm.rotate_virtual_screen(Direction::West(1)),
+        ));
+
+        self.add_keybind(KeyBinding::new(
+            MouseBind::new(MouseButton::ScrollDown)
+                .with_mod(self.config.mouse_mod_key()),
+            |wm, _| wm.rotate_virtual_screen(Direction::East(1)),
+        ));
+
+        // cycle through floating windows with the thumb buttons
+
+        self.add_keybind(KeyBinding::new(
+            MouseBind::new(MouseButton::Forward)
+                .with_mod(self.config.mouse_mod_key()),
+            |wm, _| wm.cycle_floating_focus(true),
+        ));
+
+        self.add_keybind(KeyBinding::new(
+            MouseBind::new(MouseButton::Backward)
+                .with_mod(self.config.mouse_mod_key()),
+            |wm, _| wm.cycle_floating_focus(false),
+        ));
+
+        self.add_keybind(KeyBinding::new(
+            KeyBind::new(VirtualKeyCode::L)
+                .with_mod(self.config.mod_key)
+                .with_mod(ModifierKey::Shift),
+            |wm, _| {
+                if !wm.nudge_focused(Direction::east()) {
+                    wm.rotate_virtual_screen(Direction::East(1))
+                }
+            },
+        ));
+
+        self.add_keybind(KeyBinding::new(
+            KeyBind::new(VirtualKeyCode::Tab).with_mod(self.config.mod_key),
+            |wm, _| wm.rotate_virtual_screen_back(),
+        ));
+
+        // Mod + Num
+
+        // Press Mod + `1` to move go to the `1`th virtual screen
+        self.add_keybind(KeyBinding::new(
+            KeyBind::new(VirtualKeyCode::One).with_mod(self.config.mod_key),
+            |wm, _| wm.go_to_nth_virtual_screen(1),
+        ));
+
+        // Press Mod + `2` to move go to the `2`th virtual screen
+        self.add_keybind(KeyBinding::new(
+            KeyBind::new(VirtualKeyCode::Two).with_mod(self.config.mod_key),
+            |wm, _| wm.go_to_nth_virtual_screen(2),
+        ));
+
+        // Press Mod + `3` to move go to the `3`th virtual screen
+        self.add_keybind(KeyBinding::new(
+            KeyBind::new(VirtualKeyCode::Three).with_mod(self.config.mod_key),
+            |wm, _| wm.go_to_nth_virtual_screen(3),
+        ));
+
+        // Press Mod + `4` to move go to the `4`th virtual screen
+        self.add_keybind(KeyBinding::new(
+            KeyBind::new(VirtualKeyCode::Four).with_mod(self.config.mod_key),
+            |wm, _| wm.go_to_nth_virtual_screen(4),
+        ));
+
+        // Press Mod + `5` to move go to the `5`th virtual screen
+        self.add_keybind(KeyBinding::new(
+            KeyBind::new(VirtualKeyCode::Five).with_mod(self.config.mod_key),
+            |wm, _| wm.go_to_nth_virtual_screen(5),
+        ));
+
+        // Press Mod + `6` to move go to the `6`th virtual screen
+        self.add_keybind(KeyBinding::new(
+            KeyBind::new(VirtualKeyCode::Six).with_mod(self.config.mod_key),
+            |wm, _| wm.go_to_nth_virtual_screen(6),
+        ));
+
+        // Press Mod + `7` to move go to the `7`th virtual screen
+        self.add_keybind(KeyBinding::new(
+            KeyBind::new(VirtualKeyCode::Seven).with_mod(self.config.mod_key),
+            |wm, _| wm.go_to_nth_virtual_screen(7),
+        ));
+
+        // Press Mod + `8` to move go to the `8`th virtual screen
+        self.add_keybind(KeyBinding::new(
+            KeyBind::new(VirtualKeyCode::Eight).with_mod(self.config.mod_key),
+            |wm, _| wm.go_to_nth_virtual_screen(8),
+        ));
+
+        // Press Mod + `9` to move go to the `9`th virtual screen
+        self.add_keybind(KeyBinding::new(
+            KeyBind::new(VirtualKeyCode::Nine).with_mod(self.config.mod_key),
+            |wm, _| wm.go_to_nth_virtual_screen(9),
+        ));
+
+        // Press Mod + `0` to move go to the `0`th virtual screen
+        self.add_keybind(KeyBinding::new(
+            KeyBind::new(VirtualKeyCode::Zero).with_mod(self.config.mod_key),
+            |wm, _| wm.go_to_nth_virtual_screen(10),
+        ));
+
+        // Mod + Control + Num views the `n`th virtual screen alongside the
+        // current one, Mod + Control + Shift + Num adds the focused client to it
+        let keys = [
+            VirtualKeyCode::One,
+            VirtualKeyCode::Two,
+            VirtualKeyCode::Three,
+            VirtualKeyCode::Four,
+            VirtualKeyCode::Five,
+            VirtualKeyCode::Six,
+            VirtualKeyCode::Seven,
+            VirtualKeyCode::Eight,
+            VirtualKeyCode::Nine,
+            VirtualKeyCode::Zero,
+        ];
+
+        for (i, key) in keys.iter().enumerate() {
+            let n = i + 1;
+
+            self.add_keybind(KeyBinding::new(
+                KeyBind::new(*key)
+                    .with_mod(self.config.mod_key)
+                    .with_mod(ModifierKey::Control),
+                move |wm, _| wm.toggle_nth_virtual_screen(n),
+            ));
+
+            self.add_keybind(KeyBinding::new(
+                KeyBind::new(*key)
+                    .with_mod(self.config.mod_key)
+                    .with_mod(ModifierKey::Control)
+                    .with_mod(ModifierKey::Shift),
+                move |wm, _| wm.toggle_tag_focused(n),
+            ));
+        }
+    }
+
+    #[allow(unused_mut)]
+    pub fn run(mut self) -> ! {
+        unsafe {
+            libc::signal(
+                libc::SIGTERM,
+                request_termination as extern "C" fn(libc::c_int)
+                    as libc::sighandler_t,
+            );
+            libc::signal(
+                libc::SIGCHLD,
+                child_exited as extern "C" fn(libc::c_int)
+                    as libc::sighandler_t,
+            );
+        }
+
+        self.autostart();
+
+        loop {
+            // waiting is interrupted by signals, so the timeout only bounds
+            // how long to wait without any timers
+            let timeout = self
+                .timers
+                .iter()
+                .map(|(at, _)| at.saturating_duration_since(Instant::now()))
+                .min()
+                .unwrap_or(Self::IDLE_TIMEOUT);
+            let event = self.backend.next_event_timeout(timeout);
+
+            if TERMINATION_REQUESTED.load(Ordering::SeqCst) {
+                info!("terminated by signal");
+                self.quit();
+            }
+
+            self.reap_children();
+            self.run_timers();
+
+            if let Some(event) = event {
+                self.handle_window_event(event);
+            }
+        }
+    }
+
+    fn handle_window_event(&mut self, event: WindowEvent<B::Window>) {
+        match event {
+            WindowEvent::KeyEvent(event) => {
+                if event.state == KeyState::Pressed {
+                    if self.switcher.is_some() {
+                        self.switcher_key(&event);
+                    } else {
+                        self.handle_keybinds(&KeyOrMouseBind {
+                            key: KeyOrButton::Key(event.keycode),
+                            modifiers: event.modifierstate,
+                        });
+                    }
+                }
+            }
+            WindowEvent::ButtonEvent(event) => {
+                self.button_event(&event);
+            }
+            WindowEvent::MapRequestEvent(MapEvent { window }) => {
+                if !self.clients.contains(&window) {
+                    self.new_client(window);
+                } else if self.clients.is_minimized(&window) {
+                    // clients deiconify themselves by mapping their window
+                    self.toggle_minimize(&window);
+                }
+            }
+            WindowEvent::UnmapEvent(event)
+                if self.clients.is_minimized(&event.window) =>
+            {
+                // minimized windows are unmapped by the window manager
+            }
+            WindowEvent::DestroyEvent(event) => {
+                if self.clients.contains(&event.window) {
+                    self.remove_client(event.window);
+                    self.arrange_clients();
+                }
+            }
+            WindowEvent::UnmapEvent(event) => {
+                if self.clients.contains(&event.window) {
+                    self.remove_client(event.window);
+                }
+                self.arrange_clients();
+            }
+            WindowEvent::EnterEvent(event) => {
+                self.focus_client(&event.window, false);
+            }
+            WindowEvent::MotionEvent(event) => {
+                self.do_move_resize_window(&event);
+            }
+            WindowEvent::ConfigureEvent(
+                event @ ConfigureEvent {
                     window,
-                    state,
-                }) => {
-                    if match state {
-                        FullscreenState::On => {
-                            self.clients.set_fullscreen(&window, true)
-                        }
-                        FullscreenState::Off => {
-                            self.clients.set_fullscreen(&window, false)
-                        }
-                        FullscreenState::Toggle => {
-                            self.clients.toggle_fullscreen(&window)
-                        }
-                    } {
-                        if let Some(client) =
-                            self.clients.get(&window).into_option()
-                        {
-                            self.backend.configure_window(
-                                window,
-                                None,
-                                None,
-                                if client.is_fullscreen() {
-                                    Some(0)
-                                } else {
-                                    Some(self.clients.get_border())
-                                },
-                            );
-                        };
+                    size,
+                    position,
+                    ..
+                },
+            ) => match self.clients.get_mut(&window) {
+                // docks and desktops place themselves
+                ClientEntry::Floating(client) if client.is_fixed() => {
+                    client.position = position;
+                    client.size = size;
+
+                    self.configure_client(&window);
+                }
+                ClientEntry::Tiled(_) | ClientEntry::Floating(_) => {
+                    self.configure_client(&window);
+                }
+                ClientEntry::Vacant => self
+                    .backend
+                    .handle_event(WindowEvent::ConfigureEvent(event)),
+            },
+            WindowEvent::FullscreenEvent(FullscreenEvent { window, state }) => {
+                if match state {
+                    FullscreenState::On => {
+                        self.clients.set_fullscreen(&window, true)
+                    }
+                    FullscreenState::Off => {
+                        self.clients.set_fullscreen(&window, false)
+                    }
+                    FullscreenState::Toggle => {
+                        self.clients.toggle_fullscreen(&window)
+                    }
+                } {
+                    // applies the border and frame extents of the client
+                    self.arrange_clients();
+                }
+            }
+            WindowEvent::MaximizeEvent(MaximizeEvent {
+                window,
+                vert,
+                horz,
+                state,
+            }) => {
+                let current = match self.clients.get(&window).into_option() {
+                    Some(client) => client.maximized,
+                    None => return,
+                };
+
+                let new_state = |axis: bool, current: bool| {
+                    axis.then_some(match state {
+                        MaximizeState::On => true,
+                        MaximizeState::Off => false,
+                        MaximizeState::Toggle => !current,
+                    })
+                };
+
+                if self.clients.set_maximized(
+                    &window,
+                    new_state(vert, current.0),
+                    new_state(horz, current.1),
+                ) {
+                    let (vert, horz) =
+                        self.clients.get(&window).unwrap().maximized;
+                    self.backend.set_window_maximized(window, vert, horz);
+
+                    self.arrange_clients();
+                }
+            }
+            WindowEvent::MoveResizeRequestEvent(MoveResizeRequestEvent {
+                window,
+                position,
+                size,
+            }) => match self.clients.get_mut(&window) {
+                // only floating clients get to pick their geometry
+                ClientEntry::Floating(client) => {
+                    client.position = position;
+                    client.size = size;
+
+                    self.configure_client(&window);
+                }
+                ClientEntry::Tiled(_) => {
+                    self.configure_client(&window);
+                }
+                ClientEntry::Vacant => self.backend.configure_window(
+                    window,
+                    Some(size),
+                    Some(position),
+                    None,
+                ),
+            },
+            WindowEvent::MoveResizeGrabEvent(MoveResizeGrabEvent {
+                window,
+                cursor_position,
+                action,
+            }) => {
+                self.client_move_resize(window, cursor_position, action);
+            }
+            WindowEvent::DesktopChangeEvent(DesktopChangeEvent {
+                window,
+                desktop,
+            }) => {
+                self.clients.move_to_virtualscreen(&window, desktop);
+                self.arrange_clients();
+            }
+            WindowEvent::ScreenChangeEvent(ScreenChangeEvent { size }) => {
+                info!("screen resized to {:?}", size);
+                self.clients.set_screen_size(size);
+                self.arrange_clients();
+            }
+            WindowEvent::PongEvent(PongEvent { window }) => {
+                self.pending_pings.retain(|&(pinged, _)| pinged != window);
+            }
+            WindowEvent::MinimizeEvent(MinimizeEvent { window })
+                if self.clients.contains(&window)
+                    && !self.clients.is_minimized(&window) =>
+            {
+                self.toggle_minimize(&window);
+            }
+            WindowEvent::UrgencyEvent(UrgencyEvent { window, urgent }) => {
+                // the focused client already has the user's attention
+                let urgent = urgent && !self.clients.is_focused(&window);
+                self.set_urgent(window, urgent);
+            }
+            WindowEvent::WindowNameEvent(WindowNameEvent { .. }) => {
+                info!("{:#?}", event);
+            }
+            WindowEvent::WindowTypeChangedEvent(WindowTypeChangedEvent {
+                window,
+                window_type,
+            }) => {
+                self.clients.update_window_type(&window, window_type);
+            }
+
+            // i dont think i actually have to handle destroy notify events.
+            // every window should be unmapped regardless
+            // xlib::DestroyNotify => self.destroy_notify(&event),
+            _ => {}
+        }
+    }
+
+    fn quit(&self) -> ! {
+        // TODO: should the window manager kill all clients on exit? probably
+        if self.config.kill_clients_on_exit {
+            self.clients
+                .iter_all_clients()
+                .for_each(|(&window, _)| self.backend.kill_window(window));
+        }
+
+        self.save_layout();
+        self.backend.cleanup();
+
+        info!("Goodbye.");
+
+        std::process::exit(0);
+    }
+
+    /// longest time to wait for an event while no timer is pending.
+    const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+    /// time in which the kill keybind has to be pressed again to forcefully
+    /// kill a client that doesn't support `WM_DELETE_WINDOW`.
+    const KILL_CONFIRM_TIMEOUT: Duration = Duration::from_secs(2);
+
+    fn kill_client(&mut self) {
+        if let Some((window, pid)) = self
+            .clients
+            .get_focused()
+            .into_option()
+            .map(|c| (c.window, c.pid))
+        {
+            if self.config.confirm_kill_without_delete
+                && !self.backend.can_close_window(window)
+            {
+                match self.pending_kill.take() {
+                    Some((pending, time))
+                        if pending == window
+                            && time.elapsed() < Self::KILL_CONFIRM_TIMEOUT => {}
+                    _ => {
+                        info!(
+                            "window {} doesn't support WM_DELETE_WINDOW, \
+                             press again to kill it.",
+                            window
+                        );
+                        self.pending_kill = Some((window, Instant::now()));
+                        return;
+                    }
+                }
+            }
+
+            self.pending_kill = None;
+            info!("killing window {} (pid: {:?})", window, pid);
+            let graceful = self.backend.can_close_window(window);
+            self.backend.kill_window(window);
+
+            // a client that doesn't answer the ping in time is most likely
+            // hung and won't act on `WM_DELETE_WINDOW` either
+            if graceful && self.backend.ping_window(window) {
+                self.pending_pings.push((window, Instant::now()));
+                self.add_timer(Self::PING_TIMEOUT, move |wm| {
+                    wm.check_ping(window)
+                });
+            }
+        }
+    }
+
+    /// time a client asked to close has to answer a `_NET_WM_PING` before it
+    /// is killed forcefully.
+    const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// forcefully kills `window` if it hasn't answered its ping within
+    /// `PING_TIMEOUT`.
+    fn check_ping(&mut self, window: Window) {
+        let timed_out = self.pending_pings.iter().any(|&(pinged, time)| {
+            pinged == window && time.elapsed() >= Self::PING_TIMEOUT
+        });
+
+        if timed_out {
+            self.pending_pings.retain(|&(pinged, _)| pinged != window);
+            warn!("window {} didn't answer ping, killing it.", window);
+            self.backend.force_kill_window(window);
+        }
+    }
+
+    // TODO: change this somehow cuz I'm not a big fan of this "hardcoded" keybind stuff
+    /// calls the actions bound to `bind`, returns `true` if any was called.
+    fn handle_keybinds(&mut self, bind: &KeyOrMouseBind) -> bool {
+        // I'm not sure if this has to be a Rc<RefCell>> or if it would be better as a Cell<>
+        let keybinds = self.keybinds.clone();
+        let mut handled = false;
+
+        for kb in keybinds.borrow().iter() {
+            if kb.key == *bind {
+                kb.call(self, bind);
+                handled = true;
+            }
+        }
+
+        handled
+    }
+
+    /// stops managing `window`. if it was the last tiled client on the current
+    /// virtual screen and `config.leave_empty_virtualscreen` is set, the
+    /// previous virtual screen is shown again.
+    fn remove_client(&mut self, window: Window) {
+        let current = self.clients.current_virtualscreen_index();
+        let on_current = self
+            .clients
+            .iter_workspace(current)
+            .any(|(&k, _)| k == window);
+
+        self.clients.remove(&window);
+        self.pending_pings.retain(|&(pinged, _)| pinged != window);
+        if self.scratchpad == Some(window) {
+            self.scratchpad = None;
+        }
+        self.emit(WmEvent::ClientRemoved(window));
+
+        if !self.config.leave_empty_virtualscreen
+            || !on_current
+            || self.clients.iter_workspace(current).next().is_some()
+        {
+            return;
+        }
+
+        // only go back to a virtual screen with clients on it, so closing
+        // windows never bounces between empty virtual screens
+        if self.clients.last_virtualscreen_index().is_some_and(|last| {
+            last != current
+                && self.clients.iter_workspace(last).next().is_some()
+        }) {
+            self.rotate_virtual_screen_back();
+        }
+    }
+
+    fn handle_switch_stack(&mut self) {
+        if let Some(client) =
+            self.clients.get_focused().into_option().map(|c| c.key())
+        {
+            info!("Switching stack for window {:?}", client);
+            self.clients.switch_stack_for_client(&client);
+        }
+
+        self.arrange_clients();
+    }
+
+    fn handle_promote_to_master(&mut self) {
+        if let Some(client) =
+            self.clients.get_focused().into_option().map(|c| c.key())
+        {
+            if self.clients.promote_to_master(&client) {
+                self.arrange_clients();
+            }
+        }
+    }
+
+    fn rotate_virtual_screen_back(&mut self) {
+        self.clients.rotate_back();
+
+        self.arrange_clients();
+        self.emit_workspace_changed();
+    }
+
+    fn go_to_nth_virtual_screen(&mut self, n: usize) {
+        self.clients.go_to_nth_virtualscreen(n - 1);
+        self.arrange_clients();
+        self.emit_workspace_changed();
+    }
+
+    fn toggle_nth_virtual_screen(&mut self, n: usize) {
+        self.clients.toggle_nth_virtualscreen(n - 1);
+        self.arrange_clients();
+        self.emit_workspace_changed();
+    }
+
+    fn toggle_tag_focused(&mut self, n: usize) {
+        if let Some(key) =
+            self.clients.get_focused().into_option().map(|c| c.key())
+        {
+            if self.clients.toggle_tag(&key, n - 1) {
+                self.arrange_clients();
+            }
+        }
+    }
+
+    fn emit_workspace_changed(&mut self) {
+        self.export_layout();
+        self.emit(WmEvent::WorkspaceChanged(
+            self.clients.current_virtualscreen_index(),
+        ));
+    }
+
+    fn cycle_layout(&mut self) {
+        self.clients.cycle_layout();
+        self.export_layout();
+        self.arrange_clients();
+    }
+
+    /// publishes the work area of the virtual screens for pagers and
+    /// taskbars if it changed since it was last published. struts apply to
+    /// every virtual screen, so they all share the same work area.
+    fn export_work_area(&mut self) {
+        let work_area = self.clients.get_work_area();
+
+        if self.work_area != Some(work_area) {
+            self.work_area = Some(work_area);
+
+            let n = self.clients.num_virtualscreens();
+            self.backend.set_work_areas(
+                &vec![work_area; n],
+                &vec![Point::new(0, 0); n],
+            );
+        }
+    }
+
+    /// publishes the name of the current virtual screen's layout for status
+    /// bars.
+    fn export_layout(&self) {
+        self.backend
+            .set_layout_name(self.clients.get_layout().into());
+    }
+
+    fn rotate_virtual_screen(&mut self, dir: Direction) {
+        info!("rotating VS: {:?}", dir);
 
-                        self.arrange_clients();
-                    }
-                }
-                WindowEvent::WindowNameEvent(WindowNameEvent { .. }) => {
-                    info!("{:#?}", event);
-                }
-                WindowEvent::WindowTypeChangedEvent(
-                    WindowTypeChangedEvent {
-                        window,
-                        window_type,
-                    },
-                ) => {
-                    self.clients.update_window_type(&window, window_type);
-                }
+        match dir {
+            Direction::West(n) => self.clients.rotate_left(n),
+            Direction::East(n) => self.clients.rotate_right(n),
+            _ => {}
+        }
+
+        self.arrange_clients();
+        self.emit_workspace_changed();
+    }
+
+    /// moves the focused client by `config.nudge_step` if it is floating.
+    /// returns `true` if a client was moved.
+    fn nudge_focused(&mut self, dir: Direction) -> bool {
+        let step = self.config.nudge_step;
 
-                // i dont think i actually have to handle destroy notify events.
-                // every window should be unmapped regardless
-                // xlib::DestroyNotify => self.destroy_notify(&event),
-                _ => {}
+        match self.clients.get_focused().into_option().map(|c| c.key()) {
+            Some(key) if self.clients.nudge_floating(&key, dir, step) => {
+                self.configure_client(&key);
+
+                true
             }
+            _ => false,
         }
     }
 
-    fn quit(&self) -> ! {
-        // TODO: should the window manager kill all clients on exit? probably
-        if self.config.kill_clients_on_exit {
-            self.clients
-                .iter_all_clients()
-                .for_each(|(&window, _)| self.backend.kill_window(window));
-        }
+    /// resizes the focused client by `config.resize_step` if it is floating.
+    /// returns `true` if a client was resized.
+    fn resize_focused(&mut self, dir: Direction) -> bool {
+        let step = self.config.resize_step;
 
-        info!("Goodbye.");
+        match self.clients.get_focused().into_option().map(|c| c.key()) {
+            Some(key) if self.clients.resize_floating(&key, dir, step) => {
+                self.clients.constrain_to_work_area(&key);
+                self.configure_client(&key);
 
-        std::process::exit(0);
+                true
+            }
+            _ => false,
+        }
     }
 
-    fn kill_client(&mut self) {
-        if let Some(client) = self.clients.get_focused().into_option() {
-            self.backend.kill_window(client.window);
+    /// configures the window of the client `key` to its current geometry
+    /// right away and marks that geometry as applied, so that the next
+    /// arrange reconfigures it if it differs.
+    fn configure_client<K>(&mut self, key: &K)
+    where
+        K: ClientKey,
+    {
+        self.clients.update_geometry(key);
+
+        if let Some(client) = self.clients.get(key).into_option() {
+            self.backend.configure_window(
+                client.window,
+                Some(client.size),
+                Some(client.position),
+                None,
+            );
         }
     }
 
-    // TODO: change this somehow cuz I'm not a big fan of this "hardcoded" keybind stuff
-    fn handle_keybinds(&mut self, event: &KeyEvent<B::Window>) {
-        // I'm not sure if this has to be a Rc<RefCell>> or if it would be better as a Cell<>
-        let keybinds = self.keybinds.clone();
+    /// changes the border width by `delta` and re-applies it to all windows.
+    fn change_border(&mut self, delta: i32) {
+        self.clients.set_border(self.clients.get_border() + delta);
+        self.arrange_clients();
+    }
 
-        for kb in keybinds.borrow().iter() {
-            if kb.key.key == event.keycode
-                && kb.key.modifiers == event.modifierstate
-            {
-                kb.call(self, event);
+    fn change_focused_weight(&mut self, delta: f32) {
+        if let Some(key) =
+            self.clients.get_focused().into_option().map(|c| c.key())
+        {
+            if self.clients.change_weight(&key, delta) {
+                self.arrange_clients();
             }
         }
     }
 
-    fn handle_switch_stack(&mut self) {
-        if let Some(client) =
-            self.clients.get_focused().into_option().map(|c| c.key())
+    fn toggle_minimize<K>(&mut self, key: &K)
+    where
+        K: ClientKey,
+    {
+        if let Some(window) =
+            self.clients.get(key).into_option().map(|c| c.window)
         {
-            info!("Switching stack for window {:?}", client);
-            self.clients.switch_stack_for_client(&client);
+            let was_focused = self.clients.is_focused(key);
+
+            if self.clients.toggle_minimize(key) {
+                self.backend.minimize_window(window);
+                self.arrange_clients();
+
+                // no other client took the focus
+                if was_focused && self.clients.get_focused().is_vacant() {
+                    self.backend.unfocus_window(window);
+                }
+            } else {
+                self.backend.restore_window(window);
+                self.arrange_clients();
+                self.focus_client(&window, true);
+            }
         }
+    }
 
-        self.arrange_clients();
+    /// hides the focused window and makes it the scratchpad window. a
+    /// previously stashed window is restored so it isn't lost.
+    pub fn stash_to_scratchpad(&mut self) {
+        let focused = match self.clients.get_focused().into_option() {
+            Some(client) if Some(client.window) != self.scratchpad => {
+                client.window
+            }
+            _ => return,
+        };
+
+        if let Some(old) = self.scratchpad.replace(focused) {
+            if self.clients.is_minimized(&old) {
+                self.toggle_minimize(&old);
+            }
+        }
+
+        self.toggle_minimize(&focused);
     }
 
-    fn rotate_virtual_screen_back(&mut self) {
-        self.clients.rotate_back();
+    /// shows the scratchpad window if it is hidden and hides it otherwise.
+    pub fn toggle_scratchpad(&mut self) {
+        if let Some(window) = self.scratchpad {
+            self.toggle_minimize(&window);
+        }
+    }
 
-        self.arrange_clients();
+    /// focuses the most recently urgent client. focusing clears its urgency.
+    fn focus_urgent(&mut self) {
+        if let Some(key) = self.clients.find_urgent() {
+            self.show_client(&key);
+        }
     }
 
-    fn go_to_nth_virtual_screen(&mut self, n: usize) {
-        self.clients.go_to_nth_virtualscreen(n - 1);
-        self.arrange_clients();
+    /// focuses `key`, switching to its virtual screen or restoring it if
+    /// needed.
+    fn show_client<K>(&mut self, key: &K)
+    where
+        K: ClientKey,
+    {
+        if self.clients.is_minimized(key) {
+            self.toggle_minimize(key);
+            return;
+        }
+
+        if !self.clients.is_client_visible(key) {
+            if let Some(n) = self.clients.virtualscreen_index_of(key) {
+                self.go_to_nth_virtual_screen(n + 1);
+            }
+        }
+
+        self.focus_client(key, true);
     }
 
-    fn rotate_virtual_screen(&mut self, dir: Direction) {
-        info!("rotating VS: {:?}", dir);
+    /// opens the window switcher listing the titles of all clients.
+    fn open_switcher(&mut self) {
+        let windows = self
+            .clients
+            .iter_switch_order()
+            .map(|(_, client)| client.window)
+            .collect::<Vec<_>>();
 
-        match dir {
-            Direction::West(n) => self.clients.rotate_left(n),
-            Direction::East(n) => self.clients.rotate_right(n),
-            _ => {}
+        if windows.is_empty() {
+            return;
         }
 
-        self.arrange_clients();
+        self.switcher = Some((windows, 0));
+        self.update_switcher();
+    }
+
+    fn update_switcher(&mut self) {
+        if let Some((windows, selected)) = &self.switcher {
+            let entries = windows
+                .iter()
+                .map(|&window| {
+                    self.backend.get_window_name(window).unwrap_or_default()
+                })
+                .collect::<Vec<_>>();
+
+            if !self.backend.show_switcher(&entries, *selected) {
+                self.switcher = None;
+            }
+        }
+    }
+
+    /// navigates the open window switcher, `Return` focuses the selected
+    /// client and `Escape` closes the switcher.
+    fn switcher_key(&mut self, event: &KeyEvent<B::Window>) {
+        let (windows, selected) = match self.switcher.as_mut() {
+            Some(switcher) => switcher,
+            None => return,
+        };
+
+        match event.keycode {
+            VirtualKeyCode::Down | VirtualKeyCode::J | VirtualKeyCode::Tab => {
+                *selected = (*selected + 1) % windows.len();
+                self.update_switcher();
+            }
+            VirtualKeyCode::Up | VirtualKeyCode::K => {
+                *selected = (*selected + windows.len() - 1) % windows.len();
+                self.update_switcher();
+            }
+            VirtualKeyCode::Return => {
+                let window = windows[*selected];
+
+                self.switcher = None;
+                self.backend.hide_switcher();
+                self.show_client(&window);
+            }
+            VirtualKeyCode::Escape => {
+                self.switcher = None;
+                self.backend.hide_switcher();
+            }
+            _ => {}
+        }
     }
 
     fn focus_any(&mut self) {
         // focus first client in all visible clients
-        let to_focus =
-            self.clients.iter_visible().next().map(|(k, _)| k).cloned();
+        let to_focus = self
+            .clients
+            .iter_visible()
+            .find(|&(k, _)| self.clients.is_focusable(k))
+            .map(|(k, _)| k)
+            .cloned();
 
         if let Some(key) = to_focus {
             self.focus_client(&key, false);
         }
     }
 
+    /// focuses and raises the next visible floating client, or the previous
+    /// one if `forward` is `false`. starts at the first or last floating
+    /// client if no floating client is focused.
+    fn cycle_floating_focus(&mut self, forward: bool) {
+        let floating = self
+            .clients
+            .iter_floating_visible()
+            .filter(|&(k, _)| self.clients.is_focusable(k))
+            .map(|(&k, _)| k)
+            .collect::<Vec<_>>();
+
+        if floating.is_empty() {
+            return;
+        }
+
+        let n = floating.len();
+        let next =
+            match floating.iter().position(|k| self.clients.is_focused(k)) {
+                Some(i) if forward => (i + 1) % n,
+                Some(i) => (i + n - 1) % n,
+                None if forward => 0,
+                None => n - 1,
+            };
+
+        self.focus_client(&floating[next], true);
+    }
+
     fn focus_master_stack(&mut self) {
         let focused = self.clients.get_focused().into_option().map(|c| c.key());
 
@@ -600,6 +2075,7 @@ where
             .map(|(k, _)| k)
             // get the first client on the stack thats not already focused
             .filter(|&&k| focused.map(|f| f != k).unwrap_or(true))
+            .filter(|&k| self.clients.is_focusable(k))
             .next()
             .cloned();
 
@@ -618,6 +2094,7 @@ where
             .map(|(k, _)| k)
             // get the first client on the stack thats not already focused
             .filter(|&&k| focused.map(|f| f != k).unwrap_or(true))
+            .filter(|&k| self.clients.is_focusable(k))
             .next()
             .cloned();
 
@@ -641,6 +2118,7 @@ where
                         .next()
                         .cloned()
                 })
+                .or_else(|| self.nearest_in_other_stack(focused))
         });
 
         if let Some(k) = k {
@@ -662,6 +2140,7 @@ where
                         .next()
                         .cloned()
                 })
+                .or_else(|| self.nearest_in_other_stack(focused))
         });
 
         if let Some(k) = k {
@@ -669,7 +2148,37 @@ where
         }
     }
 
+    /// returns the client in the other stack whose vertical center is closest
+    /// to that of `key`.
+    fn nearest_in_other_stack(&self, key: u64) -> Option<u64> {
+        let center = |client: &Client| client.rect().center().y;
+        let y = center(self.clients.get(&key).into_option()?);
+
+        self.clients
+            .get_other_stack_for_client(&key)?
+            .iter()
+            .filter_map(|k| {
+                self.clients
+                    .get(k)
+                    .into_option()
+                    .map(|c| (*k, (center(c) - y).abs()))
+            })
+            .min_by_key(|&(_, distance)| distance)
+            .map(|(k, _)| k)
+    }
+
+    /// focuses the nearest client in `dir`, or the next client in the stacks
+    /// if there is none.
     fn move_focus(&mut self, dir: Direction) {
+        let closest = self.clients.get_focused().into_option().and_then(|c| {
+            self.clients.closest_client_in_direction(&c.key(), dir)
+        });
+
+        if let Some(key) = closest {
+            self.focus_client(&key, true);
+            return;
+        }
+
         match dir {
             Direction::East(_) => self.focus_aux_stack(),
             Direction::West(_) => self.focus_master_stack(),
@@ -678,46 +2187,154 @@ where
         }
     }
 
-    fn hide_hidden_clients(&self) {
-        self.clients
+    fn hide_hidden_clients(&mut self) {
+        let hidden = self
+            .clients
             .iter_hidden()
-            .for_each(|(_, c)| self.backend.hide_window(c.window));
+            .map(|(&k, _)| k)
+            .collect::<Vec<_>>();
+
+        for window in hidden {
+            self.backend.hide_window(window);
+            self.clients.invalidate_geometry(&window);
+        }
     }
 
+    /// raises floating, transient and fullscreen clients in render order so
+    /// they end up stacked on top of the tiled clients. Transient clients
+    /// are raised right above their parent, those of tiled clients above all
+    /// floating clients. Pinned floating clients are raised last.
     fn raise_floating_clients(&self) {
-        self.clients
-            .iter_floating()
-            .for_each(|(_, c)| self.backend.raise_window(c.window));
+        let mut tiled = Vec::new();
+
+        for c in self.clients.iter_render_order() {
+            if self.is_pinned_floating(c) {
+                // raised last
+            } else if c.is_desktop() {
+                self.backend.lower_window(c.window);
+            } else if c
+                .parent_window
+                .is_some_and(|parent| self.clients.contains(&parent))
+            {
+                // raised along with their parent
+            } else if !self.clients.get(&c.window).is_tiled()
+                || c.is_fullscreen()
+            {
+                self.raise_client(c.window);
+            } else {
+                tiled.push(c.window);
+            }
+        }
 
-        self.clients
-            .iter_transient()
-            .for_each(|(_, c)| self.backend.raise_window(c.window));
+        for window in tiled {
+            self.raise_transients(window, &mut vec![window]);
+        }
 
-        //raise fullscreen windows
-        self.clients
-            .iter_current_screen()
-            .filter(|(_, c)| c.is_fullscreen())
-            .for_each(|(_, c)| self.backend.raise_window(c.window));
+        self.raise_pinned_clients();
+    }
+
+    fn is_pinned_floating(&self, client: &Client) -> bool {
+        client.is_pinned() && self.clients.get(&client.window).is_floating()
+    }
+
+    /// raises the pinned floating clients in render order, to put them back
+    /// on top after another client was raised.
+    fn raise_pinned_clients(&self) {
+        let pinned = self
+            .clients
+            .iter_render_order()
+            .filter(|&c| self.is_pinned_floating(c))
+            .map(|c| c.window)
+            .collect::<Vec<_>>();
+
+        for window in pinned {
+            self.raise_client(window);
+        }
+    }
+
+    /// raises `window` and then its transient clients above it.
+    fn raise_client(&self, window: Window) {
+        self.backend.raise_window(window);
+        self.raise_transients(window, &mut vec![window]);
+    }
+
+    /// raises the transient clients of `parent`, each followed by its own
+    /// transient clients. `raised` guards against cycles in the chain.
+    fn raise_transients(&self, parent: Window, raised: &mut Vec<Window>) {
+        let transients = self
+            .clients
+            .iter_transients_for(&parent)
+            .map(|(&k, _)| k)
+            .collect::<Vec<_>>();
+
+        for window in transients {
+            if !raised.contains(&window) {
+                raised.push(window);
+                self.backend.raise_window(window);
+                self.raise_transients(window, raised);
+            }
+        }
     }
 
     fn arrange_clients(&mut self) {
-        self.clients.iter_visible().for_each(|(_, c)| {
-            self.backend.move_window(c.window, c.position);
-            self.backend.resize_window(c.window, c.size);
-            //self.xlib.expose_client(c);
-        });
+        self.with_server_grabbed(Self::arrange_clients_grabbed);
+    }
+
+    fn arrange_clients_grabbed(&mut self) {
+        let windows = self
+            .clients
+            .iter_render_order()
+            .map(|c| c.window)
+            .collect::<Vec<_>>();
+
+        // only reconfigure windows whose geometry or border changed
+        for window in windows {
+            let border_changed = match self.clients.update_border(&window) {
+                Some(border) => {
+                    self.backend.set_frame_extents(window, border);
+                    true
+                }
+                None => false,
+            };
+
+            if !self.clients.update_geometry(&window) && !border_changed {
+                continue;
+            }
+
+            if let Some(c) = self.clients.get(&window).into_option() {
+                self.backend.configure_window(
+                    c.window,
+                    Some(c.size),
+                    Some(c.position),
+                    Some(c.border),
+                );
+                self.backend.notify_geometry(
+                    c.window,
+                    (c.position, c.size),
+                    c.border,
+                );
+                //self.xlib.expose_client(c);
+            }
+        }
 
         self.hide_hidden_clients();
 
         self.raise_floating_clients();
 
-        // if no visible client is focused, focus any.
+        // struts may have changed along with the clients
+        self.export_work_area();
+
+        // if no visible client is focused, focus the one last focused on
+        // this virtual screen, or any.
         if !self
             .clients
             .iter_visible()
             .any(|(k, _)| self.clients.is_focused(k))
         {
-            self.focus_any();
+            match self.clients.last_focused_on_current() {
+                Some(key) => self.focus_client(&key, false),
+                None => self.focus_any(),
+            }
         }
     }
 
@@ -725,30 +2342,131 @@ where
     where
         K: ClientKey,
     {
+        // repeated enter events for the focused window would otherwise
+        // refocus it and repaint its border
+        if self.clients.is_focused(key) {
+            return;
+        }
+
+        // e.g. docks entered by the pointer keep the focus where it is, or
+        // give it to a normal window if nothing is focused
+        if self.clients.contains(key) && !self.clients.is_focusable(key) {
+            if self.clients.get_focused().is_vacant() {
+                self.focus_any();
+            }
+            return;
+        }
+
+        let was_urgent = self.clients.is_urgent(key);
+        let zoomed = self.clients.zoomed();
         let (new, old) = self.clients.focus_client(key);
 
-        if let Some(old) = old.into_option() {
-            self.backend.unfocus_window(old.window);
+        let unfocused = old.into_option().map(|old| old.window);
+        let new = match new {
+            ClientEntry::Floating(new) => Some((new.window, true)),
+            ClientEntry::Tiled(new) => Some((new.window, false)),
+            ClientEntry::Vacant => None,
+        };
+
+        if let Some(old) = unfocused {
+            self.backend.unfocus_window(old);
+        }
+
+        // the zoom followed the focus to a hidden peer, show it first
+        if self.clients.zoomed() != zoomed {
+            self.arrange_clients();
         }
 
-        match new {
-            ClientEntry::Floating(new) => {
-                self.backend.focus_window(new.window);
+        let focused = new.map(|(window, floating)| {
+            self.backend.focus_window(window);
 
-                if try_raise {
-                    self.backend.raise_window(new.window);
-                }
+            if floating && try_raise {
+                self.raise_client(window);
+                self.raise_pinned_clients();
             }
-            ClientEntry::Tiled(new) => {
-                self.backend.focus_window(new.window);
+
+            window
+        });
+
+        if let Some(old) = unfocused {
+            self.set_opacity(old, false);
+        }
+
+        // the border widths of the focused and unfocused client changed
+        if focused.is_some() && self.clients.has_focus_dependent_border() {
+            self.arrange_clients();
+        }
+
+        if let Some(window) = focused {
+            // focusing a client clears its urgency
+            if was_urgent && !self.clients.is_urgent(&window) {
+                self.backend.set_window_urgent(window, false);
             }
-            _ => {}
+
+            self.set_opacity(window, true);
+            self.emit(WmEvent::FocusChanged(window));
+        }
+    }
+
+    /// returns the tiled terminal client from `config.swallow_terminals`
+    /// that the process `pid` was started from, if any. Terminals started
+    /// from terminals aren't swallowed.
+    fn find_terminal(&self, pid: u32) -> Option<Window> {
+        let is_terminal = |pid: u32| {
+            util::process_name(pid).is_some_and(|name| {
+                self.config.swallow_terminals.contains(&name)
+            })
+        };
+
+        if is_terminal(pid) {
+            return None;
+        }
+
+        std::iter::successors(util::parent_pid(pid), |&pid| {
+            util::parent_pid(pid)
+        })
+        .filter(|&pid| is_terminal(pid))
+        .find_map(|pid| {
+            self.clients
+                .iter_current_screen()
+                .find(|(_, c)| c.pid == Some(pid))
+                .map(|(&k, _)| k)
+        })
+    }
+
+    /// marks a client as urgent or clears its urgency, and reflects it in
+    /// the window's `_NET_WM_STATE`.
+    fn set_urgent(&mut self, window: Window, urgent: bool) {
+        if self.clients.set_urgent(&window, urgent) {
+            self.backend.set_window_urgent(window, urgent);
+        }
+    }
+
+    /// applies `config.inactive_opacity` to unfocused windows, if set.
+    fn set_opacity(&self, window: Window, focused: bool) {
+        if let Some(opacity) = self.config.inactive_opacity {
+            self.backend.set_window_opacity(
+                window,
+                if focused { 1.0 } else { opacity },
+            );
         }
     }
 
     fn new_client(&mut self, window: Window) {
         let client = match self.backend.get_window_type(window) {
             WindowType::Normal => Client::new_default(window),
+            window_type @ (WindowType::Dock | WindowType::Desktop) => {
+                let (position, size) = self
+                    .backend
+                    .get_window_rect(window)
+                    .unwrap_or(((0, 0).into(), (100, 100).into()));
+
+                Client::new_default(window)
+                    .with_window_type(window_type)
+                    .with_position(position)
+                    .with_size(size)
+                    .with_strut(self.backend.get_window_strut(window))
+            }
             window_type @ _ => Client::new_default(window)
                 .with_window_type(window_type)
                 .with_size(
@@ -756,66 +2474,178 @@ where
                         .get_window_size(window)
                         .unwrap_or((100, 100).into()),
                 )
-                .with_parent_window(self.backend.get_parent_window(window)),
+                .with_parent_window(self.backend.get_parent_window(window))
+                .with_strut(self.backend.get_window_strut(window)),
+        }
+        .with_pid(self.backend.get_window_pid(window))
+        .with_aspect(self.backend.get_window_aspect(window));
+
+        let border = if client.is_fixed() {
+            0
+        } else {
+            self.clients.get_border()
         };
+        let client = client.with_border(border);
 
-        self.backend.configure_window(
-            window,
-            None,
-            None,
-            Some(self.clients.get_border()),
-        );
+        self.backend
+            .configure_window(window, None, None, Some(border));
+        self.backend.set_frame_extents(window, border);
 
         info!("new client: {:#?}", client);
 
         self.clients.insert(client).unwrap();
+
+        if let Some(desktop) = self.backend.get_window_desktop(window) {
+            self.clients.move_to_virtualscreen(&window, desktop);
+        } else if self.clients.get(&window).is_tiled() {
+            let pid =
+                self.clients.get(&window).into_option().and_then(|c| c.pid);
+
+            if let Some(n) = self.take_pending_spawn(pid) {
+                self.clients.move_to_virtualscreen(&window, n);
+            } else if let Some(terminal) =
+                pid.and_then(|pid| self.find_terminal(pid))
+            {
+                self.clients.swallow(&window, &terminal);
+            }
+        }
+
+        self.emit(WmEvent::ClientAdded(window));
         self.arrange_clients();
 
-        self.focus_client(&window, true);
+        let demands_attention = self.backend.window_demands_attention(window);
+
+        if self.clients.is_client_visible(&window)
+            && (self.config.focus_new_windows || demands_attention)
+        {
+            self.focus_client(&window, true);
+        } else {
+            self.backend.paint_window_border(window, false);
+            self.set_opacity(window, false);
+        }
+
+        if demands_attention {
+            self.set_urgent(window, !self.clients.is_focused(&window));
+        }
     }
 
     /// ensure event.subwindow refers to a valid client.
     fn start_move_resize_window(&mut self, event: &ButtonEvent<B::Window>) {
         let window = event.window; // xev.subwindow
 
-        if !self.clients.get(&window).is_fullscreen() {
-            match event.keycode {
-                MouseButton::Left => {
-                    if self.clients.set_floating(&window) {
-                        self.arrange_clients();
-                    }
+        match event.keycode {
+            MouseButton::Left => self.start_move(window, event.cursor_position),
+            MouseButton::Right => self.start_resize(
+                window,
+                event.cursor_position,
+                // holding shift keeps the aspect ratio of the window
+                event.modifierstate.contains(ModifierState::SHIFT),
+            ),
+            _ => {}
+        }
+    }
 
-                    self.move_resize_window =
-                        MoveResizeInfo::Move(MoveInfoInner {
-                            window,
-                            starting_cursor_pos: event.cursor_position,
-                            starting_window_pos: self
-                                .clients
-                                .get(&window)
-                                .unwrap()
-                                .position,
-                        });
-                }
-                MouseButton::Right => {
-                    if self.clients.set_floating(&window) {
-                        self.arrange_clients();
-                    }
+    /// fullscreen clients, docks and desktops can't be moved or resized.
+    fn is_immovable(&self, window: &B::Window) -> bool {
+        self.clients
+            .get(window)
+            .into_option()
+            .is_none_or(|c| c.is_fullscreen() || c.is_fixed())
+    }
 
-                    let client = self.clients.get(&window).unwrap();
+    /// floats a tiled client where it is tiled, rather than where it was
+    /// last floating, so it doesn't jump away from the cursor.
+    fn float_in_place(&mut self, window: &B::Window) {
+        if let ClientEntry::Tiled(client) = self.clients.get_mut(window) {
+            client.saved_geometry = None;
+        }
 
-                    let corner_pos = client.position + client.size.into();
+        if self.clients.set_floating(window) {
+            self.arrange_clients();
+        }
+    }
 
-                    self.backend.move_cursor(None, corner_pos.into());
-                    self.backend.grab_cursor();
+    /// floats `window` and starts moving it along with the cursor.
+    fn start_move(&mut self, window: B::Window, cursor_position: Point<i32>) {
+        if self.is_immovable(&window) {
+            return;
+        }
 
-                    self.move_resize_window =
-                        MoveResizeInfo::Resize(ResizeInfoInner {
-                            window,
-                            starting_cursor_pos: corner_pos.into(),
-                            starting_window_size: client.size,
-                        });
-                }
-                _ => {}
+        let from_tiled = self.clients.get(&window).is_tiled();
+        self.float_in_place(&window);
+
+        if let Some(client) = self.clients.get(&window).into_option() {
+            // also takes over the button grab of a mouse drag to show the
+            // move cursor
+            self.backend.grab_cursor(CursorShape::Move);
+
+            self.move_resize_window = MoveResizeInfo::Move(MoveInfoInner {
+                window,
+                starting_cursor_pos: cursor_position,
+                starting_window_pos: client.position,
+                from_tiled,
+            });
+        }
+    }
+
+    /// floats `window` and starts resizing it from the corner nearest to the
+    /// cursor, keeping its current aspect ratio if `lock_aspect` is set.
+    fn start_resize(
+        &mut self,
+        window: B::Window,
+        cursor_position: Point<i32>,
+        lock_aspect: bool,
+    ) {
+        if self.is_immovable(&window) {
+            return;
+        }
+
+        self.float_in_place(&window);
+
+        if let Some(client) = self.clients.get(&window).into_option() {
+            let corner = Corner::nearest(client.rect(), cursor_position);
+            let corner_pos = corner.position(client.rect());
+
+            self.backend.move_cursor(None, corner_pos);
+            self.backend.grab_cursor(CursorShape::Resize);
+
+            self.move_resize_window = MoveResizeInfo::Resize(ResizeInfoInner {
+                window,
+                starting_cursor_pos: corner_pos,
+                starting_window_pos: client.position,
+                starting_window_size: client.size,
+                corner,
+                aspect: lock_aspect.then(|| {
+                    client.size.width as f32 / client.size.height as f32
+                }),
+            });
+        }
+    }
+
+    /// handles a `_NET_WM_MOVERESIZE` request, which clients send to let the
+    /// window manager move or resize them, e.g. when dragging a client-side
+    /// decoration.
+    fn client_move_resize(
+        &mut self,
+        window: B::Window,
+        cursor_position: Point<i32>,
+        action: MoveResizeAction,
+    ) {
+        match action {
+            MoveResizeAction::Cancel => {
+                self.move_resize_window = MoveResizeInfo::None;
+                self.backend.ungrab_cursor();
+            }
+            _ if !matches!(self.move_resize_window, MoveResizeInfo::None)
+                || !self.clients.contains(&window) => {}
+            MoveResizeAction::Move => {
+                // the client released its own pointer grab, so without the
+                // one taken by `start_move` the button release would go to
+                // the window under the cursor
+                self.start_move(window, cursor_position);
+            }
+            MoveResizeAction::Resize => {
+                self.start_resize(window, cursor_position, false);
             }
         }
     }
@@ -823,7 +2653,20 @@ where
     fn end_move_resize_window(&mut self, event: &ButtonEvent<B::Window>) {
         match event.keycode {
             MouseButton::Left => {
+                // dropping a window dragged out of the tiling onto a tiled
+                // window tiles it in its place
+                if let MoveResizeInfo::Move(info) = &self.move_resize_window {
+                    if info.from_tiled
+                        && self
+                            .clients
+                            .tile_at(&info.window, event.cursor_position)
+                    {
+                        self.arrange_clients();
+                    }
+                }
+
                 self.move_resize_window = MoveResizeInfo::None;
+                self.backend.ungrab_cursor();
             }
             MouseButton::Right => {
                 self.move_resize_window = MoveResizeInfo::None;
@@ -841,16 +2684,24 @@ where
                     event.position.y - info.starting_cursor_pos.y,
                 );
 
-                if let Some(client) =
-                    self.clients.get_mut(&info.window).into_option()
-                {
-                    let position = &mut client.position;
+                // snapping is computed from the unsnapped position, so leaving
+                // the snap zone doesn't make the window jump
+                let position = self.clients.snap_position(
+                    &info.window,
+                    info.starting_window_pos + Point::new(x, y),
+                    self.config.snap_distance,
+                );
 
-                    position.x = info.starting_window_pos.x + x;
-                    position.y = info.starting_window_pos.y + y;
+                let window = info.window;
 
-                    self.backend.move_window(client.window, client.position);
+                if let Some(client) =
+                    self.clients.get_mut(&window).into_option()
+                {
+                    client.position = position;
                 }
+
+                self.clients.constrain_to_work_area(&window);
+                self.configure_client(&window);
             }
             MoveResizeInfo::Resize(info) => {
                 let (x, y) = (
@@ -861,15 +2712,42 @@ where
                 if let Some(client) =
                     self.clients.get_mut(&info.window).into_option()
                 {
-                    let size = &mut client.size;
-
-                    size.width =
-                        std::cmp::max(1, info.starting_window_size.width + x);
-                    size.height =
-                        std::cmp::max(1, info.starting_window_size.height + y);
-
-                    self.backend.resize_window(client.window, client.size);
+                    let start_size = info.starting_window_size;
+                    let start_pos = info.starting_window_pos;
+
+                    // dragging a left or top corner grows the window in the
+                    // opposite direction and moves its origin along
+                    let (x, y) = (
+                        if info.corner.is_left() { -x } else { x },
+                        if info.corner.is_top() { -y } else { y },
+                    );
+
+                    let width = std::cmp::max(1, start_size.width + x);
+                    let height = match info.aspect {
+                        Some(aspect) => (width as f32 / aspect).round() as i32,
+                        None => start_size.height + y,
+                    };
+
+                    client.size = client
+                        .constrain_aspect(Size::new(width, height.max(1)));
+
+                    client.position = Point::new(
+                        if info.corner.is_left() {
+                            start_pos.x + start_size.width - client.size.width
+                        } else {
+                            start_pos.x
+                        },
+                        if info.corner.is_top() {
+                            start_pos.y + start_size.height - client.size.height
+                        } else {
+                            start_pos.y
+                        },
+                    );
                 }
+
+                let window = info.window;
+                self.clients.constrain_to_work_area(&window);
+                self.configure_client(&window);
             }
             _ => {}
         }
@@ -878,17 +2756,33 @@ where
     fn button_event(&mut self, event: &ButtonEvent<B::Window>) {
         match event.state {
             KeyState::Pressed => {
-                self.focus_client(&event.window, true);
+                // the thumb buttons cycle the focus from the focused floating
+                // client rather than the one under the pointer
+                if !matches!(
+                    event.keycode,
+                    MouseButton::Forward | MouseButton::Backward
+                ) {
+                    self.focus_client(&event.window, true);
+                }
+
+                if self.handle_keybinds(&KeyOrMouseBind {
+                    key: KeyOrButton::Button(event.keycode),
+                    modifiers: event.modifierstate,
+                }) {
+                    return;
+                }
 
                 match event.keycode {
                     MouseButton::Left | MouseButton::Right => {
                         match self.move_resize_window {
                             MoveResizeInfo::None
-                                if ModifierState::from([self
-                                    .config
-                                    .mod_key])
-                                .eq(&event.modifierstate)
-                                    && self.clients.contains(&event.window) =>
+                                if self.is_mouse_mod(
+                                    &event
+                                        .modifierstate
+                                        .difference(ModifierState::SHIFT),
+                                ) && self
+                                    .clients
+                                    .contains(&event.window) =>
                             {
                                 self.start_move_resize_window(event)
                             }
@@ -899,6 +2793,18 @@ where
                         self.clients.toggle_floating(&event.window);
                         self.arrange_clients();
                     }
+                    // scrolling over the root window switches virtual screens,
+                    // only presses are handled so a scroll step switches once
+                    MouseButton::ScrollUp
+                        if !self.clients.contains(&event.window) =>
+                    {
+                        self.rotate_virtual_screen(Direction::West(1));
+                    }
+                    MouseButton::ScrollDown
+                        if !self.clients.contains(&event.window) =>
+                    {
+                        self.rotate_virtual_screen(Direction::East(1));
+                    }
                     _ => {}
                 }
             }
@@ -911,27 +2817,180 @@ where
         }
     }
 
-    pub fn spawn<'a, S, I>(&self, command: S, args: I)
+    /// runs `callback` after `delay` has passed.
+    pub fn add_timer<F>(&mut self, delay: Duration, callback: F)
+    where
+        F: FnOnce(&mut Self) + 'static,
+    {
+        self.timers
+            .push((Instant::now() + delay, Box::new(callback)));
+    }
+
+    /// runs and removes the timers whose deadline has passed.
+    fn run_timers(&mut self) {
+        let now = Instant::now();
+
+        let (due, pending) = std::mem::take(&mut self.timers)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(at, _)| *at <= now);
+
+        self.timers = pending;
+
+        for (_, callback) in due {
+            callback(self);
+        }
+    }
+
+    /// waits on spawned programs that have exited so they don't linger as
+    /// zombie processes.
+    fn reap_children(&self) {
+        self.children
+            .borrow_mut()
+            .retain_mut(|child| match child.try_wait() {
+                Ok(Some(_)) | Err(_) => false,
+                Ok(None) => true,
+            });
+    }
+
+    /// time in which a program started with `spawn_on` has to map its window
+    /// for it to be moved to the requested virtual screen.
+    const SPAWN_ON_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// starts the programs in `config.autostart`.
+    fn autostart(&mut self) {
+        for (n, command, args) in self.config.autostart.clone() {
+            self.spawn_on(n, &command, &args);
+        }
+    }
+
+    /// spawns `command` and moves its first window to the `n`th virtual
+    /// screen, counting from 1, instead of the current one.
+    ///
+    /// windows are matched by `_NET_WM_PID`, also taking windows of
+    /// processes forked by the program. windows without a pid are matched
+    /// to the oldest pending program, so a different window without a pid
+    /// being mapped in the meantime can end up on the wrong virtual screen.
+    pub fn spawn_on<S, I>(&mut self, n: usize, command: S, args: I)
+    where
+        S: AsRef<str> + AsRef<std::ffi::OsStr>,
+        I: IntoIterator<Item = S> + std::fmt::Debug,
+    {
+        if n == 0 || n > self.clients.num_virtualscreens() {
+            warn!("no virtual screen {}, spawning on the current one", n);
+            let _ = self.spawn(command, args);
+            return;
+        }
+
+        if let Ok(pid) = self.spawn(command, args) {
+            self.pending_spawns.push(PendingSpawn {
+                pid: Some(pid),
+                virtualscreen: n - 1,
+                deadline: Instant::now() + Self::SPAWN_ON_TIMEOUT,
+            });
+        }
+    }
+
+    /// removes and returns the virtual screen the window of the process
+    /// `pid` should be moved to. the process or its closest ancestor has to
+    /// be a pending spawn, windows without a pid take the oldest pending
+    /// spawn. Expired entries are dropped.
+    fn take_pending_spawn(&mut self, pid: Option<u32>) -> Option<usize> {
+        let now = Instant::now();
+        self.pending_spawns.retain(|pending| pending.deadline > now);
+
+        let index = match pid {
+            Some(pid) => {
+                std::iter::successors(Some(pid), |&pid| util::parent_pid(pid))
+                    .find_map(|pid| {
+                        self.pending_spawns
+                            .iter()
+                            .position(|pending| pending.pid == Some(pid))
+                    })
+            }
+            None => (!self.pending_spawns.is_empty()).then_some(0),
+        }?;
+
+        Some(self.pending_spawns.remove(index).virtualscreen)
+    }
+
+    /// spawns `command` and returns the pid of the new process. The child
+    /// itself is kept to be reaped once it exits, which is why only its pid
+    /// is returned rather than the `Child`: a caller holding the `Child`
+    /// would have to wait on it or leave a zombie behind.
+    ///
+    /// failures are logged and, if `config.bell_on_spawn_failure` is set,
+    /// signaled by ringing the bell.
+    pub fn spawn<S, I>(&self, command: S, args: I) -> std::io::Result<u32>
     where
         S: AsRef<str> + AsRef<std::ffi::OsStr>,
         I: IntoIterator<Item = S> + std::fmt::Debug,
     {
+        use std::os::unix::process::CommandExt;
+
         info!("spawn: {:?} {:?}", AsRef::<str>::as_ref(&command), args);
-        match std::process::Command::new(AsRef::<std::ffi::OsStr>::as_ref(
-            &command,
-        ))
-        .args(args)
-        .spawn()
-        {
-            Ok(_) => {}
+
+        let mut cmd = std::process::Command::new(
+            AsRef::<std::ffi::OsStr>::as_ref(&command),
+        );
+
+        cmd.args(args)
+            .envs(self.config.env.iter().map(|(k, v)| (k, v)))
+            .stdin(std::process::Stdio::null())
+            // detach the child from the window manager's process group so
+            // it isn't affected by signals sent to the window manager.
+            .process_group(0);
+
+        if let Some(display) = self.backend.display_name() {
+            cmd.env("DISPLAY", display);
+        }
+
+        match cmd.spawn() {
+            Ok(child) => {
+                let pid = child.id();
+                self.children.borrow_mut().push(child);
+
+                Ok(pid)
+            }
             Err(err) => {
                 error!(
                     "Failed to spawn {:?}: {:?}",
                     AsRef::<str>::as_ref(&command),
                     err
                 );
+
+                if self.config.bell_on_spawn_failure {
+                    self.backend.bell();
+                }
+
+                Err(err)
+            }
+        }
+    }
+
+    /// terminals tried in order by the emergency terminal keybind.
+    const FALLBACK_TERMINALS: &'static [&'static str] =
+        &["xterm", "st", "alacritty", "urxvt"];
+
+    /// spawns the first of `commands` that can be started, returns the error
+    /// of the last one if none could.
+    pub fn spawn_first_available(
+        &self,
+        commands: &[&str],
+    ) -> std::io::Result<u32> {
+        let mut result = Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "no command to spawn",
+        ));
+
+        for &command in commands {
+            result = self.spawn(command, Vec::<&str>::new());
+
+            if result.is_ok() {
+                break;
             }
         }
+
+        result
     }
 }
 
@@ -965,3 +3024,1046 @@ impl std::ops::Not for Direction {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backends::{
+        mock::{Call, MockBackend, MockWindow},
+        window_event::{DestroyEvent, EnterEvent},
+    };
+
+    fn mock_wm() -> WindowManager<MockBackend> {
+        mock_wm_with(WMConfig {
+            gap: Some(0),
+            border_width: Some(0),
+            ..WMConfig::default()
+        })
+    }
+
+    /// creates a window manager that doesn't touch the saved layout.
+    fn mock_wm_with(config: WMConfig) -> WindowManager<MockBackend> {
+        WindowManager::new(WMConfig {
+            layout_file: None,
+            ..config
+        })
+    }
+
+    fn map(wm: &mut WindowManager<MockBackend>, window: Window) {
+        wm.handle_window_event(WindowEvent::MapRequestEvent(MapEvent {
+            window,
+        }));
+    }
+
+    #[test]
+    fn mapped_windows_are_tiled_and_focused() {
+        let mut wm = mock_wm();
+
+        map(&mut wm, 1);
+        let first = wm.backend.window(1).unwrap();
+        assert_eq!(first.position, Point::new(0, 0));
+        assert_eq!(first.size, Size::new(1000, 800));
+
+        map(&mut wm, 2);
+        let (first, second) =
+            (wm.backend.window(1).unwrap(), wm.backend.window(2).unwrap());
+        assert!(first.position.x < second.position.x);
+        assert_eq!(first.size.width + second.size.width, 1000);
+        assert!(wm.backend.take_calls().contains(&Call::Focus(2)));
+
+        wm.handle_window_event(WindowEvent::DestroyEvent(DestroyEvent::new(2)));
+        assert_eq!(wm.backend.window(1).unwrap().size, Size::new(1000, 800));
+        assert!(wm.backend.take_calls().contains(&Call::Focus(1)));
+    }
+
+    #[test]
+    fn unfocused_windows_shrink_their_border() {
+        let mut wm = mock_wm_with(WMConfig {
+            gap: Some(0),
+            border_width: Some(2),
+            unfocused_border_width: Some(0),
+            ..WMConfig::default()
+        });
+
+        map(&mut wm, 1);
+        map(&mut wm, 2);
+
+        let (first, second) =
+            (wm.backend.window(1).unwrap(), wm.backend.window(2).unwrap());
+        assert_eq!((first.border, second.border), (0, 2));
+        // the unfocused window grows into the space of its border
+        assert_eq!(first.size.width + second.size.width + 2 * 2, 1000);
+
+        wm.focus_client(&1u64, false);
+        let (first, second) =
+            (wm.backend.window(1).unwrap(), wm.backend.window(2).unwrap());
+        assert_eq!((first.border, second.border), (2, 0));
+        assert_eq!(
+            (first.frame_extents, second.frame_extents),
+            (Some(2), Some(0))
+        );
+    }
+
+    #[test]
+    fn remove_keybindings_removes_matching_binds() {
+        let bind = KeyBind::new(VirtualKeyCode::A).with_mod(ModifierKey::Super);
+        let other = KeyBind::new(VirtualKeyCode::B);
+
+        let mut keybinds: Vec<KeyBinding<XLib>> =
+            vec![KeyBinding::new(bind.clone(), |_, _| {})];
+
+        assert!(!remove_keybindings(&mut keybinds, &other.into()));
+        assert!(remove_keybindings(&mut keybinds, &bind.into()));
+        assert!(keybinds.is_empty());
+    }
+
+    #[test]
+    fn config_missing_keys_use_defaults() {
+        let config = WMConfig::from_toml_str("num_virtualscreens = 4");
+
+        assert_eq!(config.num_virtualscreens, 4);
+        assert_eq!(config.nudge_step, WMConfig::default_nudge_step());
+    }
+
+    #[test]
+    fn config_invalid_key_keeps_valid_keys() {
+        let config = WMConfig::from_toml_str(
+            "num_virtualscreens = 4\nnudge_step = \"far\"\nborder_width = 3",
+        );
+
+        assert_eq!(config.num_virtualscreens, 4);
+        assert_eq!(config.border_width, Some(3));
+        assert_eq!(config.nudge_step, WMConfig::default_nudge_step());
+    }
+
+    #[test]
+    fn spawned_windows_land_on_requested_virtualscreen() {
+        let mut wm = mock_wm();
+
+        wm.spawn_on(3, "true", Vec::<&str>::new());
+        let pid = wm.pending_spawns[0].pid;
+        assert!(pid.is_some());
+
+        wm.backend.add_window(
+            1,
+            MockWindow {
+                pid,
+                ..MockWindow::default()
+            },
+        );
+        map(&mut wm, 1);
+        assert!(!wm.clients.is_client_visible(&1u64));
+        assert_eq!(wm.clients.virtualscreen_index_of(&1u64), Some(2));
+        assert!(wm.pending_spawns.is_empty());
+
+        // the pending entry is consumed, later windows stay where they are
+        map(&mut wm, 2);
+        assert!(wm.clients.is_client_visible(&2u64));
+
+        // windows of unrelated processes don't take a pending entry, those
+        // without a pid take the oldest one
+        wm.spawn_on(2, "true", Vec::<&str>::new());
+        for (window, pid) in [(3, Some(std::process::id())), (4, None)] {
+            wm.backend.add_window(
+                window,
+                MockWindow {
+                    pid,
+                    ..MockWindow::default()
+                },
+            );
+            map(&mut wm, window);
+        }
+        assert!(wm.clients.is_client_visible(&3u64));
+        assert_eq!(wm.clients.virtualscreen_index_of(&4u64), Some(1));
+
+        // virtual screens count from 1
+        wm.spawn_on(0, "true", Vec::<&str>::new());
+        assert!(wm.pending_spawns.is_empty());
+    }
+
+    #[test]
+    fn config_parses_autostart() {
+        let config = WMConfig::from_toml_str(
+            "autostart = [[2, \"xterm\", [\"-e\", \"top\"]]]",
+        );
+        assert_eq!(
+            config.autostart,
+            vec![(2, "xterm".to_string(), vec!["-e".into(), "top".into()])]
+        );
+    }
+
+    #[test]
+    fn transient_chains_are_raised_above_their_parent() {
+        let mut wm = mock_wm();
+
+        let dialog = |parent| MockWindow {
+            window_type: WindowType::Dialog,
+            parent: Some(parent),
+            ..MockWindow::default()
+        };
+        wm.backend.add_window(2, dialog(1));
+        wm.backend.add_window(3, dialog(2));
+
+        map(&mut wm, 1);
+        map(&mut wm, 3);
+        map(&mut wm, 2);
+        map(&mut wm, 4);
+        wm.clients.set_floating(&4u64);
+
+        wm.backend.take_calls();
+        wm.arrange_clients();
+
+        let raised = |calls: Vec<Call>| {
+            calls
+                .into_iter()
+                .filter_map(|call| match call {
+                    Call::Raise(window) => Some(window),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // dialogs of tiled clients stay above floating clients
+        assert_eq!(raised(wm.backend.take_calls()), vec![4, 2, 3]);
+
+        wm.clients.set_floating(&1u64);
+        wm.focus_client(&1u64, true);
+        assert_eq!(raised(wm.backend.take_calls()), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn failed_spawn_rings_the_bell() {
+        let wm = mock_wm_with(WMConfig {
+            bell_on_spawn_failure: true,
+            ..WMConfig::default()
+        });
+        wm.backend.take_calls();
+
+        assert!(wm
+            .spawn("/nonexistent/program", Vec::<&str>::new())
+            .is_err());
+        assert_eq!(wm.backend.take_calls(), vec![Call::Bell]);
+
+        assert!(wm.spawn("true", Vec::<&str>::new()).is_ok());
+        assert!(wm.backend.take_calls().is_empty());
+    }
+
+    #[test]
+    fn layout_name_is_exported() {
+        let mut wm = mock_wm();
+        assert_eq!(wm.backend.layout_name().as_deref(), Some("MasterStack"));
+
+        wm.cycle_layout();
+        assert_eq!(wm.backend.layout_name().as_deref(), Some("Spiral"));
+    }
+
+    #[test]
+    fn arrange_only_reconfigures_changed_windows() {
+        let mut wm = mock_wm();
+        map(&mut wm, 1);
+        map(&mut wm, 2);
+
+        wm.backend.take_calls();
+        wm.arrange_clients();
+        assert!(!wm
+            .backend
+            .take_calls()
+            .iter()
+            .any(|call| matches!(call, Call::Configure(_))));
+
+        // the master client keeps its geometry
+        map(&mut wm, 3);
+        let calls = wm.backend.take_calls();
+        assert!(!calls.contains(&Call::Configure(1)));
+        assert!(calls.contains(&Call::Configure(2)));
+    }
+
+    #[test]
+    fn demands_attention_is_tracked_and_cleared() {
+        let mut wm = mock_wm();
+        map(&mut wm, 1);
+        map(&mut wm, 2);
+
+        wm.handle_window_event(WindowEvent::UrgencyEvent(UrgencyEvent::new(
+            1, true,
+        )));
+        assert!(wm.clients.is_urgent(&1u64));
+        assert!(wm.backend.window(1).unwrap().urgent);
+
+        wm.focus_client(&1u64, false);
+        assert!(!wm.clients.is_urgent(&1u64));
+        assert!(!wm.backend.window(1).unwrap().urgent);
+    }
+
+    #[test]
+    fn wm_name_and_cursor_are_passed_to_backend() {
+        let wm = mock_wm();
+        assert_eq!(wm.backend.config(), &BackendConfig::default());
+
+        let config = WMConfig::from_toml_str(
+            "wm_name = \"LG3D\"\ncursor = \"crosshair\"",
+        );
+        let wm = mock_wm_with(config);
+        assert_eq!(wm.backend.config().wm_name, "LG3D");
+        assert_eq!(wm.backend.config().cursor, "crosshair");
+    }
+
+    #[test]
+    fn unmanaged_windows_are_configured_as_requested() {
+        let mut wm = mock_wm();
+        map(&mut wm, 1);
+        wm.backend.take_calls();
+
+        let request = |window| {
+            WindowEvent::ConfigureEvent(
+                ConfigureEvent::new(
+                    window,
+                    (300, 200).into(),
+                    (400, 300).into(),
+                )
+                .with_border(Some(2)),
+            )
+        };
+
+        wm.handle_window_event(request(7));
+        assert_eq!(wm.backend.take_calls(), vec![Call::Configure(7)]);
+        let window = wm.backend.window(7).unwrap();
+        assert_eq!(
+            (window.position, window.size, window.border),
+            ((300, 200).into(), (400, 300).into(), 2)
+        );
+        assert!(!wm.clients.contains(&7u64));
+
+        // tiled windows keep their place in the layout
+        wm.handle_window_event(request(1));
+        let window = wm.backend.window(1).unwrap();
+        assert_eq!(
+            (window.position, window.size, window.border),
+            ((0, 0).into(), (1000, 800).into(), 0)
+        );
+    }
+
+    #[test]
+    fn mouse_buttons_trigger_bound_actions() {
+        let mut wm = mock_wm();
+        map(&mut wm, 1);
+
+        let modifiers = ModifierState::from([wm.config.mouse_mod_key()]);
+        let scroll = |button| {
+            WindowEvent::ButtonEvent(ButtonEvent::new(
+                1,
+                KeyState::Pressed,
+                button,
+                (0, 0).into(),
+                modifiers,
+            ))
+        };
+
+        wm.handle_window_event(scroll(MouseButton::ScrollDown));
+        assert_eq!(wm.clients.current_virtualscreen_index(), 1);
+
+        wm.handle_window_event(scroll(MouseButton::ScrollUp));
+        assert_eq!(wm.clients.current_virtualscreen_index(), 0);
+
+        wm.unbind(
+            MouseBind::new(MouseButton::ScrollDown)
+                .with_mod(wm.config.mouse_mod_key()),
+        );
+        wm.handle_window_event(scroll(MouseButton::ScrollDown));
+        assert_eq!(wm.clients.current_virtualscreen_index(), 0);
+    }
+
+    #[test]
+    fn unbinding_releases_the_extra_modifier_grab() {
+        let mut wm = mock_wm_with(WMConfig {
+            extra_mod_key: Some(ModifierKey::Alt),
+            ..WMConfig::default()
+        });
+
+        let bind = KeyOrMouseBind::from(
+            MouseBind::new(MouseButton::ScrollDown)
+                .with_mod(wm.config.mouse_mod_key()),
+        );
+        let extra = wm.extra_bind(&bind).unwrap();
+        assert!(wm.backend.keybinds().contains(&bind));
+        assert!(wm.backend.keybinds().contains(&extra));
+
+        wm.unbind(bind.clone());
+        assert!(!wm.backend.keybinds().contains(&bind));
+        assert!(!wm.backend.keybinds().contains(&extra));
+        assert!(!wm.keybinds.borrow().iter().any(|kb| kb.key == extra));
+    }
+
+    #[test]
+    fn scrolling_over_root_switches_virtual_screen() {
+        let mut wm = mock_wm();
+        map(&mut wm, 1);
+
+        let scroll = |window, button, state| {
+            WindowEvent::ButtonEvent(ButtonEvent::new(
+                window,
+                state,
+                button,
+                (0, 0).into(),
+                ModifierState::empty(),
+            ))
+        };
+
+        wm.handle_window_event(scroll(
+            0,
+            MouseButton::ScrollDown,
+            KeyState::Pressed,
+        ));
+        wm.handle_window_event(scroll(
+            0,
+            MouseButton::ScrollDown,
+            KeyState::Released,
+        ));
+        assert_eq!(wm.clients.current_virtualscreen_index(), 1);
+
+        // clients get to handle their own scrolling
+        wm.handle_window_event(scroll(
+            1,
+            MouseButton::ScrollUp,
+            KeyState::Pressed,
+        ));
+        assert_eq!(wm.clients.current_virtualscreen_index(), 1);
+    }
+
+    #[test]
+    fn arrange_flushes_once() {
+        let mut wm = mock_wm();
+        map(&mut wm, 1);
+        map(&mut wm, 2);
+        map(&mut wm, 3);
+
+        let flushes = wm.backend.flushes();
+        wm.arrange_clients();
+        assert_eq!(wm.backend.flushes(), flushes + 1);
+    }
+
+    #[test]
+    fn pinned_floating_clients_are_raised_last() {
+        let mut wm = mock_wm();
+        map(&mut wm, 1);
+        map(&mut wm, 2);
+        map(&mut wm, 3);
+
+        wm.clients.set_floating(&1u64);
+        wm.clients.set_floating(&2u64);
+        assert!(wm.clients.toggle_pin(&1u64));
+        wm.clients.toggle_fullscreen(&3u64);
+
+        wm.backend.take_calls();
+        wm.arrange_clients();
+
+        let raised = wm
+            .backend
+            .take_calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                Call::Raise(window) => Some(window),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(raised, vec![2, 3, 1]);
+
+        // focusing and raising another floating client keeps the pin on top
+        wm.focus_client(&2u64, true);
+        let raised = wm
+            .backend
+            .take_calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                Call::Raise(window) => Some(window),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(raised.last(), Some(&1));
+
+        // the pin has no effect on tiled clients
+        wm.clients.set_tiled(&1u64);
+        wm.arrange_clients();
+        assert_eq!(wm.clients.iter_render_order().last().unwrap().window, 3);
+    }
+
+    #[test]
+    fn work_area_follows_struts() {
+        let mut wm = mock_wm();
+        let n = wm.clients.num_virtualscreens();
+        assert_eq!(
+            wm.backend.work_areas(),
+            vec![Rect::new((0, 0).into(), (1000, 800).into()); n]
+        );
+
+        wm.backend.add_window(
+            1,
+            MockWindow {
+                window_type: WindowType::Dock,
+                size: Size::new(1000, 20),
+                strut: Some(Strut::new(0, 0, 20, 0)),
+                ..Default::default()
+            },
+        );
+        map(&mut wm, 1);
+        assert_eq!(
+            wm.backend.work_areas(),
+            vec![Rect::new((0, 20).into(), (1000, 780).into()); n]
+        );
+
+        wm.handle_window_event(WindowEvent::DestroyEvent(DestroyEvent::new(1)));
+        assert_eq!(
+            wm.backend.work_areas()[0],
+            Rect::new((0, 0).into(), (1000, 800).into())
+        );
+    }
+
+    #[test]
+    fn closing_last_window_leaves_empty_virtualscreen() {
+        let mut wm = mock_wm_with(WMConfig {
+            leave_empty_virtualscreen: true,
+            ..WMConfig::default()
+        });
+        map(&mut wm, 1);
+
+        wm.go_to_nth_virtual_screen(3);
+        map(&mut wm, 2);
+        map(&mut wm, 3);
+
+        wm.handle_window_event(WindowEvent::DestroyEvent(DestroyEvent::new(2)));
+        assert_eq!(wm.clients.current_virtualscreen_index(), 2);
+
+        wm.handle_window_event(WindowEvent::DestroyEvent(DestroyEvent::new(3)));
+        assert_eq!(wm.clients.current_virtualscreen_index(), 0);
+
+        // the previous virtual screen is empty, so there is nothing to go
+        // back to
+        wm.go_to_nth_virtual_screen(2);
+        map(&mut wm, 4);
+        wm.go_to_nth_virtual_screen(3);
+        wm.go_to_nth_virtual_screen(2);
+        wm.handle_window_event(WindowEvent::DestroyEvent(DestroyEvent::new(4)));
+        assert_eq!(wm.clients.current_virtualscreen_index(), 1);
+    }
+
+    #[test]
+    fn unresponsive_clients_are_killed_after_ping_timeout() {
+        let mut wm = mock_wm();
+
+        let expire = |wm: &mut WindowManager<MockBackend>| {
+            let past =
+                Instant::now() - WindowManager::<MockBackend>::PING_TIMEOUT;
+            wm.pending_pings
+                .iter_mut()
+                .for_each(|(_, time)| *time = past);
+            wm.timers.iter_mut().for_each(|(at, _)| *at = past);
+            wm.run_timers();
+        };
+
+        map(&mut wm, 1);
+        wm.backend.take_calls();
+        wm.kill_client();
+        assert_eq!(wm.backend.take_calls(), vec![Call::Kill(1), Call::Ping(1)]);
+
+        // answering the ping keeps the client alive
+        wm.handle_window_event(WindowEvent::PongEvent(PongEvent::new(1)));
+        expire(&mut wm);
+        assert!(wm.backend.take_calls().is_empty());
+
+        wm.handle_window_event(WindowEvent::DestroyEvent(DestroyEvent::new(1)));
+        map(&mut wm, 2);
+        wm.kill_client();
+        wm.backend.take_calls();
+
+        expire(&mut wm);
+        assert_eq!(wm.backend.take_calls(), vec![Call::ForceKill(2)]);
+        assert!(wm.pending_pings.is_empty());
+    }
+
+    #[test]
+    fn changing_border_retiles_windows() {
+        let mut wm = mock_wm();
+        map(&mut wm, 1);
+        map(&mut wm, 2);
+
+        let width = |wm: &WindowManager<MockBackend>, w| {
+            wm.backend.window(w).unwrap().size.width
+        };
+        let before = width(&wm, 1);
+
+        wm.change_border(3);
+        assert_eq!(wm.clients.get(&1u64).unwrap().border, 3);
+        assert_eq!(wm.backend.window(2).unwrap().border, 3);
+        assert_eq!(width(&wm, 1), before - 6);
+
+        wm.change_border(-10);
+        assert_eq!(wm.clients.get_border(), 0);
+        assert_eq!(wm.backend.window(1).unwrap().border, 0);
+    }
+
+    #[test]
+    fn spawn_first_available_skips_missing_commands() {
+        let wm = mock_wm();
+
+        assert!(wm
+            .spawn_first_available(&["nirgendwm-missing-command", "true"])
+            .is_ok());
+        assert!(wm
+            .spawn_first_available(&["nirgendwm-missing-command"])
+            .is_err());
+        assert!(wm.spawn_first_available(&[]).is_err());
+    }
+
+    #[test]
+    fn reserved_margins_shrink_work_area() {
+        let config = WMConfig::from_toml_str(
+            "gap = 0\nborder_width = 0\nreserved_top = 30\nreserved_right = 10",
+        );
+        let mut wm = mock_wm_with(config);
+        map(&mut wm, 1);
+
+        let window = wm.backend.window(1).unwrap();
+        assert_eq!(window.position, Point::new(0, 30));
+        assert_eq!(window.size, Size::new(990, 770));
+
+        // struts larger than the reserved margins take precedence
+        wm.backend.add_window(
+            2,
+            MockWindow {
+                window_type: WindowType::Dock,
+                strut: Some(Strut::new(0, 0, 50, 0)),
+                ..Default::default()
+            },
+        );
+        map(&mut wm, 2);
+        assert_eq!(
+            wm.clients.get_work_area(),
+            Rect::new((0, 50).into(), (990, 750).into())
+        );
+    }
+
+    #[test]
+    fn focus_is_restored_per_virtualscreen() {
+        let mut wm = mock_wm();
+        map(&mut wm, 1);
+        map(&mut wm, 2);
+        map(&mut wm, 3);
+        wm.focus_client(&2u64, false);
+
+        wm.go_to_nth_virtual_screen(2);
+        map(&mut wm, 4);
+        assert!(wm.clients.is_focused(&4u64));
+
+        wm.go_to_nth_virtual_screen(1);
+        assert!(wm.clients.is_focused(&2u64));
+
+        // falls back to any client if the remembered one is gone
+        wm.go_to_nth_virtual_screen(2);
+        wm.handle_window_event(WindowEvent::DestroyEvent(DestroyEvent::new(2)));
+        wm.go_to_nth_virtual_screen(1);
+        assert!(!wm.clients.get_focused().is_vacant());
+    }
+
+    #[test]
+    fn refocusing_focused_client_makes_no_calls() {
+        let mut wm = mock_wm();
+        map(&mut wm, 1);
+        map(&mut wm, 2);
+        wm.focus_client(&1u64, false);
+        wm.backend.take_calls();
+
+        wm.handle_window_event(WindowEvent::EnterEvent(EnterEvent {
+            window: 1,
+        }));
+        wm.focus_client(&1u64, true);
+        assert!(wm.backend.take_calls().is_empty());
+
+        wm.handle_window_event(WindowEvent::EnterEvent(EnterEvent {
+            window: 2,
+        }));
+        assert_eq!(
+            wm.backend.take_calls(),
+            vec![Call::Unfocus(1), Call::Focus(2)]
+        );
+    }
+
+    #[test]
+    fn stashing_to_scratchpad_swaps_windows() {
+        let mut wm = mock_wm();
+        map(&mut wm, 1);
+        map(&mut wm, 2);
+
+        wm.focus_client(&1u64, false);
+        wm.stash_to_scratchpad();
+        assert!(wm.clients.is_minimized(&1u64));
+
+        wm.toggle_scratchpad();
+        assert!(wm.clients.is_client_visible(&1u64));
+        assert!(wm.clients.is_focused(&1u64));
+        wm.toggle_scratchpad();
+        assert!(wm.clients.is_minimized(&1u64));
+
+        // stashing another window gives the old one back
+        wm.focus_client(&2u64, false);
+        wm.stash_to_scratchpad();
+        assert!(wm.clients.is_client_visible(&1u64));
+        assert!(wm.clients.is_minimized(&2u64));
+        assert_eq!(wm.scratchpad, Some(2));
+
+        wm.handle_window_event(WindowEvent::DestroyEvent(DestroyEvent::new(2)));
+        assert_eq!(wm.scratchpad, None);
+    }
+
+    #[test]
+    fn screen_change_retiles_and_resizes_fullscreen() {
+        let mut wm = mock_wm();
+        map(&mut wm, 1);
+        map(&mut wm, 2);
+        wm.clients.set_fullscreen(&2u64, true);
+
+        wm.handle_window_event(WindowEvent::ScreenChangeEvent(
+            ScreenChangeEvent::new(Size::new(2000, 1000)),
+        ));
+
+        // the fullscreen client keeps its tile next to the other one
+        assert_eq!(wm.backend.window(1).unwrap().size, Size::new(1000, 1000));
+        assert_eq!(wm.backend.window(2).unwrap().size, Size::new(2000, 1000));
+        assert_eq!(
+            wm.backend.work_areas()[0],
+            Rect::new((0, 0).into(), (2000, 1000).into())
+        );
+    }
+
+    #[test]
+    fn thumb_buttons_cycle_floating_focus() {
+        let mut wm = mock_wm();
+        let modifiers = ModifierState::from([wm.config.mouse_mod_key()]);
+        let press = |button| {
+            WindowEvent::ButtonEvent(ButtonEvent::new(
+                1,
+                KeyState::Pressed,
+                button,
+                (0, 0).into(),
+                modifiers,
+            ))
+        };
+
+        map(&mut wm, 1);
+        wm.handle_window_event(press(MouseButton::Forward));
+        assert!(wm.clients.is_focused(&1u64));
+
+        for w in 2..=4 {
+            map(&mut wm, w);
+            wm.clients.set_floating(&w);
+        }
+        wm.focus_client(&1u64, false);
+
+        wm.handle_window_event(press(MouseButton::Forward));
+        assert!(wm.clients.is_focused(&2u64));
+        wm.handle_window_event(press(MouseButton::Backward));
+        assert!(wm.clients.is_focused(&4u64));
+        wm.handle_window_event(press(MouseButton::Forward));
+        assert!(wm.clients.is_focused(&2u64));
+        wm.handle_window_event(press(MouseButton::Forward));
+        assert!(wm.clients.is_focused(&3u64));
+    }
+
+    #[test]
+    fn zooming_covers_tiling_area_and_hides_peers() {
+        let mut wm = mock_wm();
+        for w in 1..=3 {
+            map(&mut wm, w);
+        }
+        wm.backend.take_calls();
+
+        assert!(wm.clients.toggle_zoom(&2u64));
+        wm.arrange_clients();
+
+        let calls = wm.backend.take_calls();
+        assert!(calls.contains(&Call::Hide(1)));
+        assert!(calls.contains(&Call::Hide(3)));
+        assert!(!calls.contains(&Call::Hide(2)));
+        assert_eq!(wm.backend.window(2).unwrap().size, Size::new(1000, 800));
+        assert!(wm.clients.get(&2u64).is_tiled());
+
+        // removing the zoomed client restores the split
+        wm.handle_window_event(WindowEvent::DestroyEvent(DestroyEvent::new(2)));
+        assert_eq!(wm.clients.zoomed(), None);
+        assert!(wm.clients.is_client_visible(&1u64));
+        assert!(wm.clients.is_client_visible(&3u64));
+
+        assert!(wm.clients.toggle_zoom(&1u64));
+        assert!(!wm.clients.toggle_zoom(&1u64));
+        wm.arrange_clients();
+        assert_eq!(wm.backend.window(1).unwrap().size, Size::new(500, 800));
+    }
+
+    #[test]
+    fn minimize_requests_iconify_clients() {
+        let mut wm = mock_wm();
+        map(&mut wm, 1);
+        map(&mut wm, 2);
+        wm.backend.take_calls();
+
+        let minimize = WindowEvent::MinimizeEvent(MinimizeEvent::new(2));
+        wm.handle_window_event(minimize.clone());
+        assert!(wm.clients.is_minimized(&2u64));
+        assert!(wm.backend.take_calls().contains(&Call::Minimize(2)));
+
+        // repeated requests don't restore the client again
+        wm.handle_window_event(minimize);
+        assert!(wm.clients.is_minimized(&2u64));
+
+        // mapping the window deiconifies it
+        wm.handle_window_event(WindowEvent::MapRequestEvent(MapEvent {
+            window: 2,
+        }));
+        assert!(!wm.clients.is_minimized(&2u64));
+    }
+
+    #[test]
+    fn minimizing_the_last_window_unfocuses_it() {
+        let mut wm = mock_wm();
+        map(&mut wm, 1);
+        map(&mut wm, 2);
+        wm.focus_client(&2u64, false);
+        wm.backend.take_calls();
+
+        wm.toggle_minimize(&2u64);
+        assert!(wm.clients.is_focused(&1u64));
+        assert!(!wm.backend.take_calls().contains(&Call::Unfocus(2)));
+
+        wm.toggle_minimize(&1u64);
+        assert!(wm.clients.get_focused().is_vacant());
+        assert!(wm.backend.take_calls().contains(&Call::Unfocus(1)));
+
+        // restoring focuses the window again
+        wm.toggle_minimize(&1u64);
+        assert!(wm.clients.is_focused(&1u64));
+        assert!(wm.backend.take_calls().contains(&Call::Focus(1)));
+    }
+
+    #[test]
+    fn unfocusable_window_types_are_skipped() {
+        let mut wm = mock_wm();
+        for (w, window_type) in [(2, WindowType::Dock), (3, WindowType::Splash)]
+        {
+            wm.backend.add_window(
+                w,
+                MockWindow {
+                    window_type,
+                    ..Default::default()
+                },
+            );
+        }
+
+        map(&mut wm, 2);
+        map(&mut wm, 3);
+        assert!(wm.clients.get_focused().is_vacant());
+
+        wm.clients.get_mut(&3u64).into_option().unwrap().position =
+            Point::new(900, 400);
+        wm.clients.insert(Client::new_default(1));
+
+        // entering a dock while nothing is focused focuses a normal window
+        wm.handle_window_event(WindowEvent::EnterEvent(EnterEvent {
+            window: 2,
+        }));
+        assert!(wm.clients.is_focused(&1u64));
+
+        wm.focus_client(&3u64, true);
+        assert!(wm.clients.is_focused(&1u64));
+
+        // directional focus doesn't pick the splash screen east of it
+        assert_eq!(
+            wm.clients
+                .closest_client_in_direction(&1u64, Direction::East(1)),
+            None
+        );
+        wm.move_focus(Direction::East(1));
+        assert!(wm.clients.is_focused(&1u64));
+    }
+
+    #[test]
+    fn dragged_windows_are_reconfigured_when_retiled() {
+        let mut wm = mock_wm();
+        let modifiers = ModifierState::from([wm.config.mouse_mod_key()]);
+        map(&mut wm, 1);
+
+        wm.clients.toggle_all_floating();
+        wm.arrange_clients();
+
+        for (state, position) in [
+            (KeyState::Pressed, Point::new(10, 10)),
+            (KeyState::Released, Point::new(200, 200)),
+        ] {
+            if state == KeyState::Released {
+                wm.handle_window_event(WindowEvent::MotionEvent(
+                    MotionEvent::new(position, 1),
+                ));
+            }
+            wm.handle_window_event(WindowEvent::ButtonEvent(ButtonEvent::new(
+                1,
+                state,
+                MouseButton::Left,
+                position,
+                modifiers,
+            )));
+        }
+        assert_eq!(
+            wm.backend.window(1).unwrap().position,
+            Point::new(190, 190)
+        );
+
+        // tiling the window again moves it back to where it was before
+        wm.clients.toggle_all_floating();
+        wm.arrange_clients();
+        assert_eq!(wm.clients.get(&1u64).unwrap().position, Point::new(0, 0));
+        assert_eq!(wm.backend.window(1).unwrap().position, Point::new(0, 0));
+    }
+
+    #[test]
+    fn zoom_follows_focus_and_new_clients() {
+        let mut wm = mock_wm();
+        for w in 1..=3 {
+            map(&mut wm, w);
+        }
+        wm.focus_client(&2u64, false);
+        wm.clients.toggle_zoom(&2u64);
+        wm.arrange_clients();
+
+        wm.focus_down();
+        assert!(wm.clients.is_focused(&3u64));
+        assert_eq!(wm.clients.zoomed(), Some(3));
+        assert!(wm.clients.is_client_visible(&3u64));
+        assert!(!wm.clients.is_client_visible(&2u64));
+        assert_eq!(wm.backend.window(3).unwrap().size, Size::new(1000, 800));
+
+        map(&mut wm, 4);
+        assert_eq!(wm.clients.zoomed(), Some(4));
+        assert!(wm.clients.is_client_visible(&4u64));
+        assert!(wm.clients.is_focused(&4u64));
+    }
+
+    #[test]
+    fn only_windows_dragged_from_the_tiling_are_tiled_on_drop() {
+        let mut wm = mock_wm();
+        let modifiers = ModifierState::from([wm.config.mouse_mod_key()]);
+        for w in 1..=3 {
+            map(&mut wm, w);
+        }
+        wm.clients.set_floating(&3u64);
+        wm.arrange_clients();
+
+        let drag = |wm: &mut WindowManager<MockBackend>, window, from| {
+            for (state, position) in [
+                (KeyState::Pressed, from),
+                (KeyState::Released, Point::new(100, 100)),
+            ] {
+                if state == KeyState::Released {
+                    wm.handle_window_event(WindowEvent::MotionEvent(
+                        MotionEvent::new(position, window),
+                    ));
+                }
+                wm.handle_window_event(WindowEvent::ButtonEvent(
+                    ButtonEvent::new(
+                        window,
+                        state,
+                        MouseButton::Left,
+                        position,
+                        modifiers,
+                    ),
+                ));
+            }
+        };
+
+        // a floating window dropped over a tiled window stays floating
+        let from = wm.clients.get(&3u64).unwrap().position;
+        drag(&mut wm, 3, from);
+        assert!(wm.clients.get(&3u64).is_floating());
+
+        // a tiled window dragged onto another one is tiled again
+        drag(&mut wm, 2, Point::new(600, 100));
+        assert!(wm.clients.get(&2u64).is_tiled());
+    }
+
+    #[test]
+    fn toggling_virtual_screens_emits_workspace_changes() {
+        let mut wm = mock_wm();
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let hook_events = events.clone();
+        wm.on_event(Box::new(move |event| {
+            hook_events.borrow_mut().push(*event)
+        }));
+
+        wm.toggle_nth_virtual_screen(2);
+        assert_eq!(*events.borrow(), vec![WmEvent::WorkspaceChanged(0)]);
+    }
+
+    #[test]
+    fn layout_is_saved_and_restored_as_json() {
+        let path = std::env::temp_dir()
+            .join(format!("nirgendwm-layout-{}.json", std::process::id()));
+
+        let mut wm = mock_wm();
+        wm.config.layout_file = Some(path.clone());
+        map(&mut wm, 1);
+        map(&mut wm, 2);
+        wm.clients.set_floating(&2u64);
+        wm.save_layout();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(serde_json::from_str::<SavedLayout>(&content).is_ok());
+
+        let mut wm = mock_wm();
+        map(&mut wm, 1);
+        map(&mut wm, 2);
+        wm.config.layout_file = Some(path.clone());
+        wm.restore_layout();
+        assert!(wm.clients.get(&2u64).is_floating());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn resized_floating_windows_stay_within_work_area() {
+        let mut wm = mock_wm_with(WMConfig {
+            gap: Some(0),
+            border_width: Some(0),
+            constrain_floating: true,
+            ..WMConfig::default()
+        });
+        map(&mut wm, 1);
+        wm.clients.set_floating(&1u64);
+        if let Some(client) = wm.clients.get_mut(&1u64).into_option() {
+            client.position = Point::new(900, 700);
+            client.size = Size::new(50, 50);
+        }
+        wm.arrange_clients();
+
+        // growing with the keyboard
+        for _ in 0..5 {
+            assert!(wm.resize_focused(Direction::east()));
+        }
+        let window = wm.backend.window(1).unwrap();
+        assert_eq!(window.size.width, 150);
+        assert_eq!(window.position.x, 850);
+
+        // and by dragging the bottom right corner, the cursor is moved onto
+        // the corner when the drag starts
+        let modifiers = ModifierState::from([wm.config.mouse_mod_key()]);
+        wm.handle_window_event(WindowEvent::ButtonEvent(ButtonEvent::new(
+            1,
+            KeyState::Pressed,
+            MouseButton::Right,
+            Point::new(990, 740),
+            modifiers,
+        )));
+        wm.handle_window_event(WindowEvent::MotionEvent(MotionEvent::new(
+            Point::new(1000, 850),
+            1,
+        )));
+        let window = wm.backend.window(1).unwrap();
+        assert_eq!(window.size.height, 150);
+        assert_eq!(window.position.y, 650);
+    }
+}
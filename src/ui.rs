@@ -0,0 +1,215 @@
+use std::{ffi::CString, mem::MaybeUninit, ptr::NonNull};
+
+use log::warn;
+
+use x11::{
+    xft,
+    xlib::{self, Window},
+};
+
+use crate::{
+    backends::xlib::{color::XftColor, Display},
+    util::Size,
+};
+
+const FONT: &str = "monospace:size=11";
+const FOREGROUND: &str = "#eeeeee";
+const BACKGROUND: &str = "#222222";
+const SELECTED_BACKGROUND: &str = "#005577";
+/// space around each entry, in pixels
+const PADDING: i32 = 4;
+
+/// an override-redirect window listing entries, e.g. client titles, with one
+/// of them highlighted.
+pub struct Switcher {
+    dpy: Display,
+    window: Window,
+    draw: NonNull<xft::XftDraw>,
+    font: NonNull<xft::XftFont>,
+    foreground: XftColor,
+    background: XftColor,
+    selected_background: XftColor,
+    screen_size: Size<i32>,
+    entries: Vec<String>,
+    selected: usize,
+}
+
+impl Switcher {
+    /// creates the switcher window, it is mapped by the first call to
+    /// `update`.
+    pub fn new(
+        dpy: Display,
+        screen: i32,
+        root: Window,
+        screen_size: Size<i32>,
+    ) -> Option<Self> {
+        let color = |name: &str| {
+            XftColor::new(dpy.clone(), screen, name.to_owned()).ok()
+        };
+
+        let foreground = color(FOREGROUND)?;
+        let background = color(BACKGROUND)?;
+        let selected_background = color(SELECTED_BACKGROUND)?;
+
+        let font_name = CString::new(FONT).ok()?;
+
+        unsafe {
+            let font = NonNull::new(xft::XftFontOpenName(
+                dpy.get(),
+                screen,
+                font_name.as_ptr(),
+            ))?;
+
+            let mut attributes =
+                MaybeUninit::<xlib::XSetWindowAttributes>::zeroed()
+                    .assume_init();
+            attributes.override_redirect = 1;
+            attributes.background_pixel = background.pixel();
+            attributes.event_mask = xlib::ExposureMask;
+
+            let visual = xlib::XDefaultVisual(dpy.get(), screen);
+
+            let window = xlib::XCreateWindow(
+                dpy.get(),
+                root,
+                0,
+                0,
+                1,
+                1,
+                0,
+                xlib::XDefaultDepth(dpy.get(), screen),
+                xlib::InputOutput as u32,
+                visual,
+                xlib::CWOverrideRedirect
+                    | xlib::CWBackPixel
+                    | xlib::CWEventMask,
+                &mut attributes,
+            );
+
+            let draw = match NonNull::new(xft::XftDrawCreate(
+                dpy.get(),
+                window,
+                visual,
+                xlib::XDefaultColormap(dpy.get(), screen),
+            )) {
+                Some(draw) => draw,
+                None => {
+                    xlib::XDestroyWindow(dpy.get(), window);
+                    xft::XftFontClose(dpy.get(), font.as_ptr());
+                    return None;
+                }
+            };
+
+            Some(Self {
+                dpy,
+                window,
+                draw,
+                font,
+                foreground,
+                background,
+                selected_background,
+                screen_size,
+                entries: Vec::new(),
+                selected: 0,
+            })
+        }
+    }
+
+    pub fn window(&self) -> Window {
+        self.window
+    }
+
+    fn line_height(&self) -> i32 {
+        unsafe { self.font.as_ref().height + PADDING * 2 }
+    }
+
+    /// replaces the listed entries and the highlighted entry, then resizes
+    /// the window to fit them centered on the screen and redraws it.
+    pub fn update(&mut self, entries: &[String], selected: usize) {
+        self.entries = entries.to_vec();
+        self.selected = selected.min(self.entries.len().saturating_sub(1));
+
+        let size = Size::new(
+            self.screen_size.width / 3,
+            self.line_height() * self.entries.len().max(1) as i32,
+        );
+
+        let (width, height) = match size.to_u32() {
+            Some(unsigned) => unsigned.as_tuple(),
+            None => {
+                warn!("not showing switcher with invalid size {:?}", size);
+                return;
+            }
+        };
+
+        unsafe {
+            xlib::XMoveResizeWindow(
+                self.dpy.get(),
+                self.window,
+                (self.screen_size.width - size.width) / 2,
+                (self.screen_size.height - size.height) / 2,
+                width.max(1),
+                height.max(1),
+            );
+            xlib::XMapRaised(self.dpy.get(), self.window);
+        }
+
+        self.draw();
+    }
+
+    pub fn draw(&self) {
+        let line_height = self.line_height();
+        let ascent = unsafe { self.font.as_ref().ascent };
+
+        let (width, height) =
+            match Size::new(self.screen_size.width / 3, line_height).to_u32() {
+                Some(unsigned) => unsigned.as_tuple(),
+                None => return,
+            };
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            let y = line_height * i as i32;
+
+            let background = if i == self.selected {
+                &self.selected_background
+            } else {
+                &self.background
+            };
+
+            unsafe {
+                xft::XftDrawRect(
+                    self.draw.as_ptr(),
+                    background.as_ptr(),
+                    0,
+                    y,
+                    width.max(1),
+                    height,
+                );
+
+                xft::XftDrawStringUtf8(
+                    self.draw.as_ptr(),
+                    self.foreground.as_ptr(),
+                    self.font.as_ptr(),
+                    PADDING,
+                    y + PADDING + ascent,
+                    entry.as_ptr(),
+                    entry.len() as i32,
+                );
+            }
+        }
+
+        unsafe {
+            xlib::XFlush(self.dpy.get());
+        }
+    }
+}
+
+impl Drop for Switcher {
+    fn drop(&mut self) {
+        unsafe {
+            xft::XftDrawDestroy(self.draw.as_ptr());
+            xft::XftFontClose(self.dpy.get(), self.font.as_ptr());
+            xlib::XDestroyWindow(self.dpy.get(), self.window);
+        }
+    }
+}
@@ -23,7 +23,44 @@ impl Hasher for IdentityHasher {
 
 pub type BuildIdentityHasher = BuildHasherDefault<IdentityHasher>;
 
+/// returns the id of the parent of process `pid`, read from `/proc`.
+pub fn parent_pid(pid: u32) -> Option<u32> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+
+    // the process name is wrapped in parentheses and may contain spaces,
+    // the state and parent id follow it.
+    let (_, rest) = stat.rsplit_once(')')?;
+    rest.split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()
+        .filter(|&ppid| ppid != 0)
+}
+
+/// returns the name of the executable of process `pid`, read from `/proc`.
+/// `comm` is truncated to 15 characters, so the file name of `exe` is used,
+/// or that of the first argument if `exe` can't be read.
+pub fn process_name(pid: u32) -> Option<String> {
+    let file_name = |path: &std::path::Path| {
+        path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+    };
+
+    std::fs::read_link(format!("/proc/{}/exe", pid))
+        .ok()
+        .and_then(|exe| file_name(&exe))
+        .or_else(|| {
+            let cmdline =
+                std::fs::read_to_string(format!("/proc/{}/cmdline", pid))
+                    .ok()?;
+            let program = cmdline.split('\0').next()?;
+
+            file_name(std::path::Path::new(program))
+        })
+}
+
 pub use point::Point;
+pub use rect::Rect;
 pub use size::Size;
 
 mod size {
@@ -136,6 +173,28 @@ mod size {
         {
             f(self.width, self.height)
         }
+
+        /// converts both dimensions to `T`, or returns `None` if either
+        /// doesn't fit, e.g. a negative width for an unsigned `T`.
+        pub fn cast<T>(self) -> Option<Size<T>>
+        where
+            T: num_traits::PrimInt + num_traits::Zero,
+        {
+            Some(Size::new(
+                num_traits::cast(self.width)?,
+                num_traits::cast(self.height)?,
+            ))
+        }
+
+        /// unsigned dimensions as X expects them, `None` if either is
+        /// negative.
+        pub fn to_u32(self) -> Option<Size<u32>> {
+            self.cast()
+        }
+
+        pub fn to_i32(self) -> Option<Size<i32>> {
+            self.cast()
+        }
     }
 }
 
@@ -242,5 +301,157 @@ mod point {
         {
             f(self.x, self.y)
         }
+
+        /// converts both coordinates to `T`, or returns `None` if either
+        /// doesn't fit.
+        pub fn cast<T>(self) -> Option<Point<T>>
+        where
+            T: num_traits::PrimInt + num_traits::Zero,
+        {
+            Some(Point::new(
+                num_traits::cast(self.x)?,
+                num_traits::cast(self.y)?,
+            ))
+        }
+
+        pub fn to_u32(self) -> Option<Point<u32>> {
+            self.cast()
+        }
+
+        pub fn to_i32(self) -> Option<Point<i32>> {
+            self.cast()
+        }
+    }
+}
+
+mod rect {
+    use super::{Point, Size};
+
+    /// an area on the screen with its top left corner at `origin`.
+    #[derive(Debug, Copy, Clone, PartialEq, Default)]
+    pub struct Rect {
+        pub origin: Point<i32>,
+        pub size: Size<i32>,
+    }
+
+    impl From<(Point<i32>, Size<i32>)> for Rect {
+        fn from((origin, size): (Point<i32>, Size<i32>)) -> Self {
+            Self::new(origin, size)
+        }
+    }
+
+    impl From<Rect> for (Point<i32>, Size<i32>) {
+        fn from(rect: Rect) -> Self {
+            (rect.origin, rect.size)
+        }
+    }
+
+    impl Rect {
+        pub fn new(origin: Point<i32>, size: Size<i32>) -> Self {
+            Self { origin, size }
+        }
+
+        /// x coordinate just past the right edge.
+        pub fn right(&self) -> i32 {
+            self.origin.x + self.size.width
+        }
+
+        /// y coordinate just past the bottom edge.
+        pub fn bottom(&self) -> i32 {
+            self.origin.y + self.size.height
+        }
+
+        pub fn center(&self) -> Point<i32> {
+            self.origin + Point::new(self.size.width / 2, self.size.height / 2)
+        }
+
+        /// returns true if `point` lies within the rectangle, the right and
+        /// bottom edges are exclusive.
+        pub fn contains(&self, point: Point<i32>) -> bool {
+            point.x >= self.origin.x
+                && point.y >= self.origin.y
+                && point.x < self.right()
+                && point.y < self.bottom()
+        }
+
+        /// returns the area covered by both rectangles, or `None` if they
+        /// don't overlap.
+        pub fn intersect(&self, other: &Self) -> Option<Self> {
+            let origin = Point::new(
+                self.origin.x.max(other.origin.x),
+                self.origin.y.max(other.origin.y),
+            );
+            let size = Size::new(
+                self.right().min(other.right()) - origin.x,
+                self.bottom().min(other.bottom()) - origin.y,
+            );
+
+            (size.width > 0 && size.height > 0)
+                .then_some(Self::new(origin, size))
+        }
+
+        /// shrinks the rectangle by `amount` on every side, a negative
+        /// `amount` grows it.
+        pub fn inset(&self, amount: i32) -> Self {
+            Self::new(
+                self.origin + Point::new(amount, amount),
+                self.size - Size::new(amount * 2, amount * 2),
+            )
+        }
+
+        /// shrinks `other` to fit within the rectangle and moves it inside,
+        /// keeping it in place if it already is.
+        pub fn fit(&self, other: &Self) -> Self {
+            let size = other.size.clamp(self.size);
+            let origin = Point::new(
+                other
+                    .origin
+                    .x
+                    .min(self.right() - size.width)
+                    .max(self.origin.x),
+                other
+                    .origin
+                    .y
+                    .min(self.bottom() - size.height)
+                    .max(self.origin.y),
+            );
+
+            Self::new(origin, size)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rect_inset_and_containment() {
+        let a = Rect::new(Point::new(0, 0), Size::new(100, 50));
+
+        assert_eq!(a.inset(5), Rect::new(Point::new(5, 5), Size::new(90, 40)));
+        assert_eq!(a.center(), Point::new(50, 25));
+        assert!(a.contains(Point::new(99, 49)));
+        assert!(!a.contains(Point::new(100, 0)));
+
+        assert_eq!(
+            a.intersect(&Rect::new(Point::new(50, 25), Size::new(100, 100))),
+            Some(Rect::new(Point::new(50, 25), Size::new(50, 25)))
+        );
+        // rectangles that only touch don't overlap
+        assert_eq!(
+            a.intersect(&Rect::new(Point::new(100, 0), Size::new(10, 10))),
+            None
+        );
+    }
+
+    #[test]
+    fn process_name_is_not_truncated() {
+        let exe = std::env::current_exe().unwrap();
+        let name = exe.file_name().unwrap().to_string_lossy();
+
+        // test binaries are named `<crate>-<hash>`, longer than `comm` allows
+        assert!(name.len() > 15);
+        assert_eq!(process_name(std::process::id()).as_deref(), Some(&*name));
     }
 }